@@ -0,0 +1,289 @@
+use anyhow::{Result, bail};
+use serde_json::json;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    rpc_request::RpcRequest,
+    rpc_response::{Response, RpcKeyedAccount},
+};
+use solana_sdk::{
+    account::Account as SolanaAccount,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::create_account;
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::{
+    extension::{
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        transfer_fee::TransferFeeAmount,
+    },
+    instruction::initialize_account,
+    state::{Account, Mint},
+};
+
+use crate::{
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// A keypair-based token account created for a mint.
+#[derive(Debug)]
+pub struct CreatedTokenAccount {
+    pub address: Pubkey,
+    pub signature: Signature,
+    pub state: Account,
+}
+
+/// A token account found by [`get_token_accounts_by_owner`].
+#[derive(Debug)]
+pub struct OwnedTokenAccount {
+    pub address: Pubkey,
+    pub program: TokenProgram,
+    pub lamports: u64,
+    pub state: Account,
+    /// Transfer fees withheld on a Token-2022 account, which block closing it.
+    pub withheld_fees: u64,
+}
+
+/// An associated token account created for an owner and mint.
+#[derive(Debug)]
+pub struct CreatedAta {
+    pub address: Pubkey,
+    pub signature: Signature,
+    pub state: Account,
+}
+
+/// An owner's associated token account from [`get_or_create_ata`].
+#[derive(Debug)]
+pub struct Ata {
+    pub address: Pubkey,
+    /// The transaction that created it, `None` when it already existed.
+    pub signature: Option<Signature>,
+    pub state: Account,
+}
+
+impl Ata {
+    pub fn created(&self) -> bool {
+        self.signature.is_some()
+    }
+}
+
+/// Fetch and unpack a token account of either token program.
+pub async fn get_token_account(client: &RpcClient, address: &Pubkey) -> Result<Account> {
+    let account = client.get_account(address).await?;
+    TokenProgram::from_owner(&account.owner)?;
+    Ok(StateWithExtensions::<Account>::unpack(&account.data)?.base)
+}
+
+/// Every token account of `mint`, found by scanning the accounts of its program.
+///
+/// This needs an rpc node that serves `getProgramAccounts` for the token programs.
+pub async fn get_token_accounts_by_mint(
+    client: &RpcClient,
+    program: TokenProgram,
+    mint: &Pubkey,
+) -> Result<Vec<(Pubkey, Account)>> {
+    //The mint is the first field of a token account. Token-2022 accounts have no fixed size,
+    //so anything else matching the prefix is dropped when it fails to unpack
+    let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new(
+        0,
+        MemcmpEncodedBytes::Base58(mint.to_string()),
+    ))];
+    if program == TokenProgram::Token {
+        filters.push(RpcFilterType::DataSize(Account::LEN as u64));
+    }
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = client
+        .get_program_accounts_with_config(&program.id(), config)
+        .await?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let state = StateWithExtensions::<Account>::unpack(&account.data).ok()?;
+            Some((address, state.base))
+        })
+        .collect())
+}
+
+/// Every token account owned by `owner`, across both token programs.
+pub async fn get_token_accounts_by_owner(
+    client: &RpcClient,
+    owner: &Pubkey,
+) -> Result<Vec<OwnedTokenAccount>> {
+    let mut accounts = Vec::new();
+    for program in [TokenProgram::Token, TokenProgram::Token2022] {
+        //The client helper asks for jsonParsed, raw bytes are needed to unpack the state
+        let response: Response<Vec<RpcKeyedAccount>> = client
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([
+                    owner.to_string(),
+                    { "programId": program.id().to_string() },
+                    { "encoding": "base64", "commitment": client.commitment().commitment },
+                ]),
+            )
+            .await?;
+        for keyed in response.value {
+            let Some(account) = keyed.account.decode::<SolanaAccount>() else {
+                continue;
+            };
+            let Ok(state) = StateWithExtensions::<Account>::unpack(&account.data) else {
+                continue;
+            };
+            let withheld_fees = state
+                .get_extension::<TransferFeeAmount>()
+                .map_or(0, |fees| fees.withheld_amount.into());
+            accounts.push(OwnedTokenAccount {
+                address: keyed.pubkey.parse()?,
+                program,
+                lamports: account.lamports,
+                state: state.base,
+                withheld_fees,
+            });
+        }
+    }
+    Ok(accounts)
+}
+
+/// Create a token account at a fresh keypair address, owned by `owner`.
+///
+/// For Token-2022 the account is sized for the extensions the mint requires.
+pub async fn create_token_account(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedTokenAccount>> {
+    let token_account = Keypair::new();
+    let space = token_account_len(client, program, mint).await?;
+    let rent = client.get_minimum_balance_for_rent_exemption(space).await?;
+
+    //Create the space, then initialize the Token-account type on it
+    let create_account_instruction = create_account(
+        &payer.pubkey(),
+        &token_account.pubkey(),
+        rent,
+        space as u64,
+        &program.id(),
+    );
+    let initialize_account_instruction =
+        initialize_account(&program.id(), &token_account.pubkey(), mint, owner)?;
+
+    let address = token_account.pubkey();
+    send_instructions(
+        client,
+        &[create_account_instruction, initialize_account_instruction],
+        &payer.pubkey(),
+        &[payer, &token_account],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(CreatedTokenAccount {
+            address,
+            signature,
+            state: get_token_account(client, &address).await?,
+        })
+    })
+    .await
+}
+
+/// Create the associated token account of `owner` for `mint`.
+///
+/// Uses the idempotent instruction, so an ATA that already exists is left as is rather than
+/// failing the transaction.
+pub async fn create_ata(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedAta>> {
+    let address = program.ata_address(owner, mint);
+
+    //ATA instruction does both creating the space and initializing the ATA type
+    let instruction =
+        create_associated_token_account_idempotent(&payer.pubkey(), owner, mint, &program.id());
+
+    send_instructions(client, &[instruction], &payer.pubkey(), &[payer], options)
+        .await?
+        .and_then(|signature| async move {
+            Ok(CreatedAta {
+                address,
+                signature,
+                state: get_token_account(client, &address).await?,
+            })
+        })
+        .await
+}
+
+/// The associated token account of `owner` for `mint`, created only when it does not exist.
+pub async fn get_or_create_ata(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Ata>> {
+    let address = program.ata_address(owner, mint);
+    let existing = client
+        .get_account_with_commitment(&address, client.commitment())
+        .await?
+        .value;
+    let Some(account) = existing else {
+        let created = create_ata(client, payer, program, mint, owner, options).await?;
+        return Ok(created.map(|created| Ata {
+            address,
+            signature: Some(created.signature),
+            state: created.state,
+        }));
+    };
+
+    TokenProgram::from_owner(&account.owner)?;
+    let state = StateWithExtensions::<Account>::unpack(&account.data)?.base;
+    //The original token program lets an ATA's owner be reassigned
+    if state.owner != *owner {
+        bail!(
+            "ATA {address} of {owner} has been reassigned to {}",
+            state.owner
+        );
+    }
+    Ok(SendOutcome::Sent(Ata {
+        address,
+        signature: None,
+        state,
+    }))
+}
+
+async fn token_account_len(
+    client: &RpcClient,
+    program: TokenProgram,
+    mint: &Pubkey,
+) -> Result<usize> {
+    if program == TokenProgram::Token {
+        return Ok(Account::LEN);
+    }
+    let mint_account = client.get_account(mint).await?;
+    let mint_extensions =
+        StateWithExtensions::<Mint>::unpack(&mint_account.data)?.get_extension_types()?;
+    let required = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+    Ok(ExtensionType::try_calculate_account_len::<Account>(
+        &required,
+    )?)
+}
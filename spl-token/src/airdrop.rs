@@ -0,0 +1,246 @@
+use anyhow::{Result, bail};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcError,
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+use crate::{
+    compute_budget::ComputeBudget,
+    signer::KeypairSource,
+    transaction::{SendOptions, send_instructions},
+};
+
+/// Keypair spec of the wallet that tops up accounts when the faucet refuses.
+pub const FUNDER_ENV: &str = "SPL_TOKEN_FUNDER";
+
+/// How hard [`ensure_funded`] tries the faucet before falling back to the funder.
+pub struct FundingOptions {
+    /// Lamports to ask the faucet for, at least the shortfall (defaults to the shortfall).
+    pub airdrop_lamports: Option<u64>,
+    /// Give up on the faucet after this long.
+    pub timeout: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Wallet that transfers the shortfall when the airdrop is refused or never lands.
+    pub funder: Option<Keypair>,
+    /// Compute budget of the funder's transfer.
+    pub compute_budget: ComputeBudget,
+}
+
+impl Default for FundingOptions {
+    fn default() -> Self {
+        Self {
+            airdrop_lamports: None,
+            timeout: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            funder: None,
+            compute_budget: ComputeBudget::DEFAULT,
+        }
+    }
+}
+
+impl FundingOptions {
+    /// Defaults, with the funder loaded from `SPL_TOKEN_FUNDER` when it is set.
+    pub fn from_env() -> Result<Self> {
+        dotenvy::dotenv().ok();
+        let funder = match std::env::var(FUNDER_ENV) {
+            Ok(spec) => Some(spec.parse::<KeypairSource>()?.load()?),
+            Err(_) => None,
+        };
+        Ok(Self {
+            funder,
+            ..Self::default()
+        })
+    }
+}
+
+/// How [`ensure_funded`] got the account to its minimum balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Funded {
+    AlreadyFunded {
+        balance: u64,
+    },
+    Airdropped {
+        signature: Signature,
+        lamports: u64,
+        balance: u64,
+    },
+    /// The faucet did not deliver, for the reason in `refusal`.
+    TransferredFromFunder {
+        signature: Signature,
+        funder: Pubkey,
+        lamports: u64,
+        refusal: String,
+        balance: u64,
+    },
+}
+
+impl Funded {
+    pub fn balance(&self) -> u64 {
+        match self {
+            Funded::AlreadyFunded { balance }
+            | Funded::Airdropped { balance, .. }
+            | Funded::TransferredFromFunder { balance, .. } => *balance,
+        }
+    }
+}
+
+impl fmt::Display for Funded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Funded::AlreadyFunded { balance } => {
+                write!(f, "already funded with {balance} lamports")
+            }
+            Funded::Airdropped {
+                signature,
+                lamports,
+                balance,
+            } => write!(
+                f,
+                "airdropped {lamports} lamports in {signature}, balance {balance}"
+            ),
+            Funded::TransferredFromFunder {
+                signature,
+                funder,
+                lamports,
+                refusal,
+                balance,
+            } => write!(
+                f,
+                "{refusal}, transferred {lamports} lamports from {funder} in {signature}, \
+                 balance {balance}"
+            ),
+        }
+    }
+}
+
+/// Whether the faucet refused the request because of rate limits or an empty faucet.
+pub fn is_rate_limited(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Reqwest(err) => err.status().map(|status| status.as_u16()) == Some(429),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            let message = message.to_lowercase();
+            *code == 429
+                || message.contains("rate limit")
+                || message.contains("airdrop limit")
+                || message.contains("request limit")
+                || message.contains("faucet")
+        }
+        _ => false,
+    }
+}
+
+/// Request an airdrop of `lamports` and wait, with backoff, until it is confirmed.
+pub async fn airdrop(client: &RpcClient, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+    let options = FundingOptions::default();
+    let signature = client.request_airdrop(pubkey, lamports).await?;
+    if !wait_for_confirmation(client, &signature, &options).await? {
+        bail!(
+            "airdrop {signature} was not confirmed within {:?}",
+            options.timeout
+        );
+    }
+    Ok(signature)
+}
+
+/// Make sure `pubkey` holds at least `min_lamports`.
+///
+/// The faucet is tried first, polling with exponential backoff until `options.timeout`.
+/// When it rate limits, fails or never confirms, the shortfall is transferred from
+/// `options.funder` instead (if one is configured).
+pub async fn ensure_funded(
+    client: &RpcClient,
+    pubkey: &Pubkey,
+    min_lamports: u64,
+    options: &FundingOptions,
+) -> Result<Funded> {
+    let balance = client.get_balance(pubkey).await?;
+    if balance >= min_lamports {
+        return Ok(Funded::AlreadyFunded { balance });
+    }
+    let shortfall = min_lamports - balance;
+    let lamports = options.airdrop_lamports.unwrap_or(shortfall).max(shortfall);
+
+    let refusal = match client.request_airdrop(pubkey, lamports).await {
+        Ok(signature) => match wait_for_confirmation(client, &signature, options).await {
+            Ok(true) => {
+                return Ok(Funded::Airdropped {
+                    signature,
+                    lamports,
+                    balance: client.get_balance(pubkey).await?,
+                });
+            }
+            Ok(false) => format!(
+                "airdrop {signature} was not confirmed within {:?}",
+                options.timeout
+            ),
+            Err(err) => format!("airdrop {signature} failed: {err}"),
+        },
+        Err(err) if is_rate_limited(&err) => format!("faucet rate limited the airdrop: {err}"),
+        Err(err) => format!("faucet refused the airdrop: {err}"),
+    };
+
+    let Some(funder) = &options.funder else {
+        bail!("{refusal}, and no funder wallet is configured (set {FUNDER_ENV})");
+    };
+    let signature = send_instructions(
+        client,
+        &[transfer(&funder.pubkey(), pubkey, shortfall)],
+        &funder.pubkey(),
+        &[funder],
+        &SendOptions {
+            compute_budget: options.compute_budget,
+            ..SendOptions::default()
+        },
+    )
+    .await?
+    .sent()?;
+    Ok(Funded::TransferredFromFunder {
+        signature,
+        funder: funder.pubkey(),
+        lamports: shortfall,
+        refusal,
+        balance: client.get_balance(pubkey).await?,
+    })
+}
+
+/// Poll the signature status with exponential backoff.
+///
+/// Returns `false` once the deadline passes without confirmation.
+async fn wait_for_confirmation(
+    client: &RpcClient,
+    signature: &Signature,
+    options: &FundingOptions,
+) -> Result<bool> {
+    let deadline = Instant::now() + options.timeout;
+    let mut backoff = options.initial_backoff;
+    loop {
+        match client.get_signature_status(signature).await {
+            Ok(Some(Ok(()))) => return Ok(true),
+            Ok(Some(Err(err))) => bail!(err),
+            Ok(None) => {}
+            // Status polling hit the rate limit as well, just wait longer
+            Err(err) if is_rate_limited(&err) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        sleep(backoff.min(deadline - now)).await;
+        backoff = (backoff * 2).min(options.max_backoff);
+    }
+}
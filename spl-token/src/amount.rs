@@ -0,0 +1,57 @@
+use anyhow::{Context, Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::mint::get_mint;
+
+/// Convert a decimal amount like `1.5` into base units of a mint with `decimals`.
+///
+/// Rejects more fractional digits than the mint supports and amounts that overflow a `u64`.
+pub fn ui_amount_to_amount(ui_amount: &str, decimals: u8) -> Result<u64> {
+    let ui_amount = ui_amount.trim();
+    let (whole, fraction) = ui_amount.split_once('.').unwrap_or((ui_amount, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        bail!("invalid amount `{ui_amount}`");
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        bail!("invalid amount `{ui_amount}`, expected a decimal number");
+    }
+    if fraction.len() > decimals as usize {
+        bail!("amount `{ui_amount}` has more than {decimals} decimal places");
+    }
+
+    let overflow = || format!("amount `{ui_amount}` is too large for {decimals} decimals");
+    let scale = 10u64.checked_pow(decimals.into()).with_context(overflow)?;
+    let whole = match whole {
+        "" => 0,
+        whole => whole.parse::<u64>().ok().with_context(overflow)?,
+    };
+    let fraction = match fraction {
+        "" => 0,
+        fraction => fraction.parse::<u64>()? * 10u64.pow(decimals as u32 - fraction.len() as u32),
+    };
+    whole
+        .checked_mul(scale)
+        .and_then(|whole| whole.checked_add(fraction))
+        .with_context(overflow)
+}
+
+/// Convert a decimal amount into base units of `mint`, using the decimals stored on chain.
+pub async fn parse_ui_amount(client: &RpcClient, mint: &Pubkey, ui_amount: &str) -> Result<u64> {
+    let decimals = get_mint(client, mint).await?.decimals;
+    ui_amount_to_amount(ui_amount, decimals)
+}
+
+/// Format base units as a decimal amount, without trailing zeros.
+pub fn amount_to_ui_amount(amount: u64, decimals: u8) -> String {
+    let digits = format!("{amount:0>width$}", width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{whole}.{fraction}"),
+    }
+}
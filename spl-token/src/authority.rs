@@ -0,0 +1,144 @@
+use anyhow::{Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_token_2022_interface::{
+    extension::{
+        BaseStateWithExtensions, StateWithExtensions, mint_close_authority::MintCloseAuthority,
+    },
+    instruction::{AuthorityType, set_authority as set_authority_instruction},
+    state::{Account, Mint},
+};
+
+use crate::{
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Result of a `set_authority`, with the authority read back afterwards.
+#[derive(Debug)]
+pub struct AuthorityChange {
+    pub signature: Signature,
+    pub previous: Option<Pubkey>,
+    pub current: Option<Pubkey>,
+}
+
+/// The key currently holding `authority_type` over `target`, `None` once revoked.
+///
+/// `MintTokens` and `FreezeAccount` are read from a mint, `AccountOwner` from a token account.
+/// `CloseAccount` is read from a token account, which is closed by its owner when it has no
+/// close authority, or from a Token-2022 mint with the `MintCloseAuthority` extension.
+pub async fn get_authority(
+    client: &RpcClient,
+    target: &Pubkey,
+    authority_type: &AuthorityType,
+) -> Result<Option<Pubkey>> {
+    Ok(resolve_authority(client, target, authority_type).await?.1)
+}
+
+/// The current authority, along with the type that designates it in a `SetAuthority`
+/// instruction: the close authority of a mint is `CloseMint` rather than `CloseAccount`.
+async fn resolve_authority(
+    client: &RpcClient,
+    target: &Pubkey,
+    authority_type: &AuthorityType,
+) -> Result<(AuthorityType, Option<Pubkey>)> {
+    let account = client.get_account(target).await?;
+    TokenProgram::from_owner(&account.owner)?;
+    let authority = match authority_type {
+        AuthorityType::MintTokens => StateWithExtensions::<Mint>::unpack(&account.data)?
+            .base
+            .mint_authority
+            .into(),
+        AuthorityType::FreezeAccount => StateWithExtensions::<Mint>::unpack(&account.data)?
+            .base
+            .freeze_authority
+            .into(),
+        AuthorityType::AccountOwner => Some(
+            StateWithExtensions::<Account>::unpack(&account.data)?
+                .base
+                .owner,
+        ),
+        AuthorityType::CloseAccount | AuthorityType::CloseMint => {
+            if let Ok(state) = StateWithExtensions::<Account>::unpack(&account.data)
+                && *authority_type == AuthorityType::CloseAccount
+            {
+                Some(state.base.close_authority.unwrap_or(state.base.owner))
+            } else {
+                let mint = StateWithExtensions::<Mint>::unpack(&account.data)?;
+                let Ok(extension) = mint.get_extension::<MintCloseAuthority>() else {
+                    bail!("mint {target} has no MintCloseAuthority extension");
+                };
+                return Ok((AuthorityType::CloseMint, extension.close_authority.into()));
+            }
+        }
+        other => bail!("{other:?} authorities are not supported"),
+    };
+    Ok((authority_type.clone(), authority))
+}
+
+/// Hand `authority_type` over `target` to `new_authority`, or revoke it for good with `None`.
+///
+/// The current authority is checked on-chain first and must be `authority`, which also pays
+/// the fee. Revoking `MintTokens` fixes the supply; an account owner cannot be revoked.
+pub async fn set_authority(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    program: TokenProgram,
+    target: &Pubkey,
+    authority_type: AuthorityType,
+    new_authority: Option<&Pubkey>,
+    options: &SendOptions,
+) -> Result<SendOutcome<AuthorityChange>> {
+    let (instruction_type, previous) = resolve_authority(client, target, &authority_type).await?;
+    match previous {
+        None => bail!("the {authority_type:?} authority of {target} is already revoked"),
+        Some(current) if current != authority.pubkey() => bail!(
+            "the {authority_type:?} authority of {target} is {current}, not {}",
+            authority.pubkey()
+        ),
+        Some(_) => {}
+    }
+    if authority_type == AuthorityType::AccountOwner && new_authority.is_none() {
+        bail!("the owner of a token account cannot be revoked");
+    }
+
+    let instruction = set_authority_instruction(
+        &program.id(),
+        target,
+        new_authority,
+        instruction_type,
+        &authority.pubkey(),
+        &[&authority.pubkey()],
+    )?;
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(AuthorityChange {
+            signature,
+            previous,
+            current: get_authority(client, target, &authority_type).await?,
+        })
+    })
+    .await
+}
+
+/// Parse `mint`, `freeze`, `owner` or `close` into an authority type.
+pub fn parse_authority_type(s: &str) -> Result<AuthorityType> {
+    match s {
+        "mint" | "mint-tokens" => Ok(AuthorityType::MintTokens),
+        "freeze" | "freeze-account" => Ok(AuthorityType::FreezeAccount),
+        "owner" | "account-owner" => Ok(AuthorityType::AccountOwner),
+        "close" | "close-account" => Ok(AuthorityType::CloseAccount),
+        "close-mint" => Ok(AuthorityType::CloseMint),
+        other => bail!("unknown authority type `{other}`, expected mint, freeze, owner or close"),
+    }
+}
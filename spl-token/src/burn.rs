@@ -0,0 +1,99 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_token_2022_interface::{
+    instruction::{burn as burn_instruction, burn_checked as burn_checked_instruction},
+    state::{Account, Mint},
+};
+
+use crate::{
+    account::get_token_account,
+    mint::get_mint,
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Result of a burn, with the mint and token account read back afterwards.
+#[derive(Debug)]
+pub struct Burned {
+    pub signature: Signature,
+    pub mint: Mint,
+    pub account: Account,
+}
+
+/// Burn `amount` base units from `account`, reducing the supply of `mint`.
+///
+/// `authority` is the account owner or its delegate, and also pays the fee.
+pub async fn burn(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome<Burned>> {
+    let instruction = burn_instruction(
+        &program.id(),
+        account,
+        mint,
+        &authority.pubkey(),
+        &[&authority.pubkey()],
+        amount,
+    )?;
+    send_burn(client, authority, instruction, account, mint, options).await
+}
+
+/// Like [`burn`], but the program also checks `decimals` against the mint.
+#[allow(clippy::too_many_arguments)]
+pub async fn burn_checked(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<Burned>> {
+    let instruction = burn_checked_instruction(
+        &program.id(),
+        account,
+        mint,
+        &authority.pubkey(),
+        &[&authority.pubkey()],
+        amount,
+        decimals,
+    )?;
+    send_burn(client, authority, instruction, account, mint, options).await
+}
+
+async fn send_burn(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    instruction: Instruction,
+    account: &Pubkey,
+    mint: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Burned>> {
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(Burned {
+            signature,
+            mint: get_mint(client, mint).await?,
+            account: get_token_account(client, account).await?,
+        })
+    })
+    .await
+}
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    program_option::COption,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_token_2022_interface::{
+    instruction::close_account as close_account_instruction, state::AccountState,
+};
+
+use crate::{
+    account::{OwnedTokenAccount, get_token_accounts_by_owner},
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Close instructions packed into one transaction by [`sweep_empty_accounts`].
+pub const CLOSE_BATCH_SIZE: usize = 20;
+
+/// A closed token account and the rent it returned.
+#[derive(Debug)]
+pub struct Closed {
+    pub signature: Signature,
+    pub address: Pubkey,
+    pub lamports: u64,
+}
+
+/// Result of [`sweep_empty_accounts`].
+#[derive(Debug, Default)]
+pub struct Sweep {
+    pub closed: Vec<Closed>,
+    /// Empty accounts left open: frozen, holding withheld fees or closable by someone else.
+    pub skipped: Vec<Pubkey>,
+}
+
+impl Sweep {
+    pub fn lamports(&self) -> u64 {
+        self.closed.iter().map(|closed| closed.lamports).sum()
+    }
+}
+
+/// Close an empty token account, sending its rent to `destination`.
+///
+/// `authority` is the account's close authority (its owner unless set) and also pays the fee.
+pub async fn close_account(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    destination: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Closed>> {
+    let lamports = client.get_balance(account).await?;
+    let instruction = close_account_instruction(
+        &program.id(),
+        account,
+        destination,
+        &authority.pubkey(),
+        &[&authority.pubkey()],
+    )?;
+
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(Closed {
+            signature,
+            address: *account,
+            lamports,
+        })
+    })
+    .await
+}
+
+/// Close every zero-balance token account of `owner`, in batches of [`CLOSE_BATCH_SIZE`],
+/// sending the rent to `destination`.
+///
+/// Accounts the owner cannot close are skipped. A failing batch stops the sweep. Unless sent,
+/// the sweep has to fit a single batch.
+pub async fn sweep_empty_accounts(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    destination: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Sweep>> {
+    let mut sweep = Sweep::default();
+    let mut closable = Vec::new();
+    for account in get_token_accounts_by_owner(client, &owner.pubkey()).await? {
+        if account.state.amount != 0 {
+            continue;
+        }
+        if can_close(&account, &owner.pubkey()) {
+            closable.push(account);
+        } else {
+            sweep.skipped.push(account.address);
+        }
+    }
+
+    let batches = closable.chunks(CLOSE_BATCH_SIZE);
+    options.mode.ensure_single(batches.len())?;
+    for batch in batches {
+        let instructions = batch
+            .iter()
+            .map(|account| {
+                close_account_instruction(
+                    &account.program.id(),
+                    &account.address,
+                    destination,
+                    &owner.pubkey(),
+                    &[&owner.pubkey()],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let outcome =
+            send_instructions(client, &instructions, &owner.pubkey(), &[owner], options).await?;
+        //Not sent, so this is the only batch
+        let SendOutcome::Sent(signature) = outcome else {
+            return Ok(outcome.map(|_| sweep));
+        };
+
+        sweep.closed.extend(batch.iter().map(|account| Closed {
+            signature,
+            address: account.address,
+            lamports: account.lamports,
+        }));
+    }
+    Ok(SendOutcome::Sent(sweep))
+}
+
+fn can_close(account: &OwnedTokenAccount, owner: &Pubkey) -> bool {
+    let close_authority = match account.state.close_authority {
+        COption::Some(authority) => authority,
+        COption::None => account.state.owner,
+    };
+    close_authority == *owner
+        && account.state.state != AccountState::Frozen
+        && account.withheld_fees == 0
+}
@@ -0,0 +1,412 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use std::{collections::HashMap, env, fmt, fs, path::PathBuf, str::FromStr};
+
+pub const LOCALNET_URL: &str = "http://localhost:8899";
+pub const DEVNET_URL: &str = "https://api.devnet.solana.com/";
+pub const TESTNET_URL: &str = "https://api.testnet.solana.com/";
+pub const MAINNET_URL: &str = "https://api.mainnet-beta.solana.com/";
+
+/// Profile file looked up in the working directory when no `--config` is given.
+pub const DEFAULT_PROFILE_FILE: &str = "spl-token.toml";
+
+pub const CLUSTER_ENV: &str = "SPL_TOKEN_CLUSTER";
+pub const WS_URL_ENV: &str = "SPL_TOKEN_WS_URL";
+pub const COMMITMENT_ENV: &str = "SPL_TOKEN_COMMITMENT";
+pub const PROFILE_ENV: &str = "SPL_TOKEN_PROFILE";
+pub const CONFIG_ENV: &str = "SPL_TOKEN_CONFIG";
+
+/// A well known cluster or a custom rpc url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cluster {
+    Localnet,
+    Devnet,
+    Testnet,
+    Mainnet,
+    Custom(String),
+}
+
+impl Cluster {
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Localnet => LOCALNET_URL,
+            Cluster::Devnet => DEVNET_URL,
+            Cluster::Testnet => TESTNET_URL,
+            Cluster::Mainnet => MAINNET_URL,
+            Cluster::Custom(url) => url,
+        }
+    }
+
+    /// Websocket url matching the rpc url, the same way the Solana CLI derives it
+    /// (`http` -> `ws`, `https` -> `wss`, and the rpc port + 1 for explicit ports).
+    pub fn ws_url(&self) -> Result<String> {
+        let url = self.url();
+        let (scheme, rest) = match url.split_once("://") {
+            Some(("https", rest)) => ("wss", rest),
+            Some((_, rest)) => ("ws", rest),
+            None => ("ws", url),
+        };
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let authority = match authority.rsplit_once(':') {
+            Some((host, port)) => match port.parse::<u16>() {
+                Ok(port) => {
+                    let ws_port = port
+                        .checked_add(1)
+                        .with_context(|| format!("no websocket port follows {port} in {url}"))?;
+                    format!("{host}:{ws_port}")
+                }
+                Err(_) => authority.to_string(),
+            },
+            None => authority.to_string(),
+        };
+        Ok(format!("{scheme}://{authority}{path}"))
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "localnet" | "localhost" | "l" => Ok(Cluster::Localnet),
+            "devnet" | "d" => Ok(Cluster::Devnet),
+            "testnet" | "t" => Ok(Cluster::Testnet),
+            "mainnet" | "mainnet-beta" | "m" => Ok(Cluster::Mainnet),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Cluster::Custom(url.to_string()))
+            }
+            other => bail!(
+                "unknown cluster `{other}`, expected localnet, devnet, testnet, mainnet or an http(s) url"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.url())
+    }
+}
+
+/// One named entry of the TOML profile file.
+///
+/// ```toml
+/// default = "local"
+///
+/// [profiles.local]
+/// cluster = "localnet"
+/// commitment = "processed"
+///
+/// [profiles.helius]
+/// cluster = "https://devnet.helius-rpc.com/?api-key=..."
+/// ws_url = "wss://devnet.helius-rpc.com/?api-key=..."
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub cluster: String,
+    pub ws_url: Option<String>,
+    pub commitment: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileFile {
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+impl ProfileFile {
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read profile file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse profile file {}", path.display()))
+    }
+}
+
+/// What was asked for on the command line or in the environment.
+///
+/// Anything left unset falls back to the selected profile, then to devnet with
+/// `confirmed` commitment.
+#[derive(Debug, Default, Clone)]
+pub struct ClusterSelection {
+    pub cluster: Option<Cluster>,
+    pub ws_url: Option<String>,
+    pub commitment: Option<CommitmentConfig>,
+    pub profile: Option<String>,
+    pub config_path: Option<PathBuf>,
+}
+
+impl ClusterSelection {
+    /// Read the selection from `SPL_TOKEN_*` variables, including ones set in `.env`.
+    pub fn from_env() -> Result<Self> {
+        dotenvy::dotenv().ok();
+        Ok(Self {
+            cluster: env::var(CLUSTER_ENV).ok().map(|s| s.parse()).transpose()?,
+            ws_url: env::var(WS_URL_ENV).ok(),
+            commitment: env::var(COMMITMENT_ENV)
+                .ok()
+                .map(|s| parse_commitment(&s))
+                .transpose()?,
+            profile: env::var(PROFILE_ENV).ok(),
+            config_path: env::var(CONFIG_ENV).ok().map(PathBuf::from),
+        })
+    }
+}
+
+/// The resolved cluster every rpc client is built from.
+#[derive(Debug, Clone)]
+pub struct ClusterConfig {
+    pub url: String,
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+}
+
+impl ClusterConfig {
+    pub fn new(cluster: Cluster) -> Result<Self> {
+        Ok(Self {
+            url: cluster.url().to_string(),
+            ws_url: cluster.ws_url()?,
+            commitment: CommitmentConfig::confirmed(),
+        })
+    }
+
+    /// Resolve the environment selection, see [`ClusterSelection::from_env`].
+    pub fn from_env() -> Result<Self> {
+        Self::resolve(ClusterSelection::from_env()?)
+    }
+
+    /// Merge the selection with the profile file: explicit values win over the profile,
+    /// and the profile wins over the devnet defaults.
+    pub fn resolve(selection: ClusterSelection) -> Result<Self> {
+        let profile = load_profile(&selection)?;
+
+        let cluster = match (selection.cluster.clone(), &profile) {
+            (Some(cluster), _) => cluster,
+            (None, Some(profile)) => profile.cluster.parse()?,
+            (None, None) => Cluster::Devnet,
+        };
+        // A profile's websocket url only makes sense with the profile's own cluster
+        let ws_url = match selection
+            .ws_url
+            .or_else(|| match (&selection.cluster, &profile) {
+                (None, Some(profile)) => profile.ws_url.clone(),
+                _ => None,
+            }) {
+            Some(ws_url) => ws_url,
+            None => cluster.ws_url()?,
+        };
+        let commitment = match (selection.commitment, &profile) {
+            (Some(commitment), _) => commitment,
+            (
+                None,
+                Some(Profile {
+                    commitment: Some(commitment),
+                    ..
+                }),
+            ) => parse_commitment(commitment)?,
+            _ => CommitmentConfig::confirmed(),
+        };
+
+        Ok(Self {
+            url: cluster.url().to_string(),
+            ws_url,
+            commitment,
+        })
+    }
+
+    /// Make a connection with the rpc using the configured commitment.
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.url.clone(), self.commitment)
+    }
+}
+
+pub fn parse_commitment(s: &str) -> Result<CommitmentConfig> {
+    CommitmentConfig::from_str(s).map_err(|_| {
+        anyhow!("invalid commitment `{s}`, expected processed, confirmed or finalized")
+    })
+}
+
+fn load_profile(selection: &ClusterSelection) -> Result<Option<Profile>> {
+    let path = match &selection.config_path {
+        Some(path) => path.clone(),
+        None => {
+            let path = PathBuf::from(DEFAULT_PROFILE_FILE);
+            if !path.exists() {
+                if let Some(name) = &selection.profile {
+                    bail!("profile `{name}` requested but {DEFAULT_PROFILE_FILE} does not exist");
+                }
+                return Ok(None);
+            }
+            path
+        }
+    };
+
+    let mut file = ProfileFile::load(&path)?;
+    let Some(name) = selection.profile.clone().or(file.default.take()) else {
+        return Ok(None);
+    };
+    file.profiles
+        .remove(&name)
+        .map(Some)
+        .with_context(|| format!("profile `{name}` not found in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A profile file with a `local` default and a `custom` profile carrying its own ws url.
+    fn profile_file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("spl-token-{name}-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"
+default = "local"
+
+[profiles.local]
+cluster = "localnet"
+commitment = "processed"
+
+[profiles.custom]
+cluster = "https://rpc.example.com:8899/"
+ws_url = "wss://ws.example.com/"
+"#,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn ws_url_follows_the_rpc_url() {
+        assert_eq!(
+            Cluster::Devnet.ws_url().unwrap(),
+            "wss://api.devnet.solana.com/"
+        );
+        assert_eq!(Cluster::Localnet.ws_url().unwrap(), "ws://localhost:8900");
+        let custom = Cluster::Custom("https://rpc.example.com:443/path?key=1".to_string());
+        assert_eq!(
+            custom.ws_url().unwrap(),
+            "wss://rpc.example.com:444/path?key=1"
+        );
+    }
+
+    #[test]
+    fn ws_url_rejects_the_last_port() {
+        let cluster = Cluster::Custom("http://localhost:65535".to_string());
+        assert!(cluster.ws_url().is_err());
+        assert!(ClusterConfig::new(cluster).is_err());
+    }
+
+    #[test]
+    fn defaults_to_devnet_without_a_profile() {
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            config_path: Some(env::temp_dir().join("spl-token-missing.toml")),
+            ..ClusterSelection::default()
+        });
+        //An explicit file has to exist
+        assert!(resolved.is_err());
+
+        let path = env::temp_dir().join(format!("spl-token-empty-{}.toml", std::process::id()));
+        fs::write(&path, "").unwrap();
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            config_path: Some(path.clone()),
+            ..ClusterSelection::default()
+        })
+        .unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(resolved.url, DEVNET_URL);
+        assert_eq!(resolved.ws_url, "wss://api.devnet.solana.com/");
+        assert_eq!(resolved.commitment, CommitmentConfig::confirmed());
+    }
+
+    #[test]
+    fn profile_fills_what_the_selection_leaves_unset() {
+        let path = profile_file("default");
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            config_path: Some(path.clone()),
+            ..ClusterSelection::default()
+        })
+        .unwrap();
+        assert_eq!(resolved.url, LOCALNET_URL);
+        assert_eq!(resolved.commitment, CommitmentConfig::processed());
+
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            profile: Some("custom".to_string()),
+            config_path: Some(path.clone()),
+            ..ClusterSelection::default()
+        })
+        .unwrap();
+        assert_eq!(resolved.url, "https://rpc.example.com:8899/");
+        assert_eq!(resolved.ws_url, "wss://ws.example.com/");
+        assert_eq!(resolved.commitment, CommitmentConfig::confirmed());
+
+        assert!(
+            ClusterConfig::resolve(ClusterSelection {
+                profile: Some("missing".to_string()),
+                config_path: Some(path.clone()),
+                ..ClusterSelection::default()
+            })
+            .is_err()
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn selection_wins_over_the_profile() {
+        let path = profile_file("selection");
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            cluster: Some(Cluster::Testnet),
+            commitment: Some(CommitmentConfig::finalized()),
+            profile: Some("custom".to_string()),
+            config_path: Some(path.clone()),
+            ..ClusterSelection::default()
+        })
+        .unwrap();
+        assert_eq!(resolved.url, TESTNET_URL);
+        //The profile's ws url belongs to the profile's cluster
+        assert_eq!(resolved.ws_url, "wss://api.testnet.solana.com/");
+        assert_eq!(resolved.commitment, CommitmentConfig::finalized());
+
+        let resolved = ClusterConfig::resolve(ClusterSelection {
+            ws_url: Some("ws://override:1".to_string()),
+            profile: Some("custom".to_string()),
+            config_path: Some(path.clone()),
+            ..ClusterSelection::default()
+        })
+        .unwrap();
+        assert_eq!(resolved.url, "https://rpc.example.com:8899/");
+        assert_eq!(resolved.ws_url, "ws://override:1");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn environment_selects_like_the_flags() {
+        let path = profile_file("env");
+        //The only test reading these variables
+        unsafe {
+            env::set_var(CLUSTER_ENV, "mainnet");
+            env::set_var(COMMITMENT_ENV, "finalized");
+            env::set_var(CONFIG_ENV, &path);
+        }
+        let selection = ClusterSelection::from_env();
+        unsafe {
+            env::remove_var(CLUSTER_ENV);
+            env::remove_var(COMMITMENT_ENV);
+            env::remove_var(CONFIG_ENV);
+        }
+        let selection = selection.unwrap();
+        assert_eq!(selection.cluster, Some(Cluster::Mainnet));
+        assert_eq!(selection.config_path, Some(path.clone()));
+
+        //The environment wins over the profile's localnet and processed commitment
+        let resolved = ClusterConfig::resolve(selection).unwrap();
+        assert_eq!(resolved.url, MAINNET_URL);
+        assert_eq!(resolved.commitment, CommitmentConfig::finalized());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result, bail};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    instruction::Instruction, message::AddressLookupTableAccount, pubkey::Pubkey, signature::Signer,
+};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use crate::transaction::sign_transaction;
+
+/// Priority fee policy, e.g. `auto`, in the `SPL_TOKEN_PRIORITY_FEE` environment variable.
+pub const PRIORITY_FEE_ENV: &str = "SPL_TOKEN_PRIORITY_FEE";
+
+/// Most compute units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Accounts `getRecentPrioritizationFees` accepts per request.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// How the compute unit price of a transaction is chosen, in micro-lamports per unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityFee {
+    None,
+    Fixed(u64),
+    /// This percentile of the fees recently paid by transactions writing the same accounts.
+    Recent {
+        percentile: u8,
+    },
+}
+
+/// Compute budget instructions put in front of a transaction, see
+/// [`SendOptions`](crate::transaction::SendOptions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// Simulate the transaction and request the units it consumed plus this many percent.
+    /// `None` keeps the cluster's default limit.
+    pub unit_margin: Option<u32>,
+    pub priority_fee: PriorityFee,
+    /// Cap on the compute unit price, whatever the policy.
+    pub max_unit_price: Option<u64>,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl ComputeBudget {
+    pub const DEFAULT: Self = Self {
+        unit_margin: Some(20),
+        priority_fee: PriorityFee::Recent { percentile: 50 },
+        max_unit_price: None,
+    };
+
+    /// Budget instructions for a transaction carrying `instructions`, to go in front of them.
+    ///
+    /// Measuring the units simulates a transaction signed by `signers`, which fails early
+    /// with the program logs when the instructions would.
+    pub async fn instructions(
+        &self,
+        client: &RpcClient,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&dyn Signer],
+    ) -> Result<Vec<Instruction>> {
+        self.instructions_with_lookup_tables(client, instructions, payer, signers, &[])
+            .await
+    }
+
+    /// Like [`ComputeBudget::instructions`], for a v0 transaction using `lookup_tables`.
+    pub async fn instructions_with_lookup_tables(
+        &self,
+        client: &RpcClient,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &[&dyn Signer],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<Vec<Instruction>> {
+        let unit_price = self.unit_price(client, instructions).await?;
+        let mut budget = Vec::new();
+        if let Some(margin) = self.unit_margin {
+            let units = simulate_units(
+                client,
+                instructions,
+                unit_price,
+                payer,
+                signers,
+                lookup_tables,
+            )
+            .await?;
+            let limit = units.saturating_mul(100 + u64::from(margin)) / 100;
+            budget.push(ComputeBudgetInstruction::set_compute_unit_limit(
+                limit.min(MAX_COMPUTE_UNIT_LIMIT.into()) as u32,
+            ));
+        }
+        if unit_price > 0 {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_price(unit_price));
+        }
+        Ok(budget)
+    }
+
+    /// Compute unit price for a transaction carrying `instructions`.
+    pub async fn unit_price(
+        &self,
+        client: &RpcClient,
+        instructions: &[Instruction],
+    ) -> Result<u64> {
+        let price = match self.priority_fee {
+            PriorityFee::None => 0,
+            PriorityFee::Fixed(price) => price,
+            PriorityFee::Recent { percentile } => {
+                //Fees are local to the accounts a transaction locks for writing
+                let writable: BTreeSet<Pubkey> = instructions
+                    .iter()
+                    .flat_map(|instruction| &instruction.accounts)
+                    .filter(|meta| meta.is_writable)
+                    .map(|meta| meta.pubkey)
+                    .collect();
+                let writable: Vec<Pubkey> = writable.into_iter().take(MAX_FEE_ACCOUNTS).collect();
+                let fees = client.get_recent_prioritization_fees(&writable).await?;
+                fee_percentile(
+                    fees.into_iter().map(|fee| fee.prioritization_fee).collect(),
+                    percentile,
+                )
+            }
+        };
+        Ok(self.max_unit_price.map_or(price, |max| price.min(max)))
+    }
+}
+
+/// The largest budget instructions, to reserve their space when packing a transaction.
+pub(crate) fn reserved_instructions() -> [Instruction; 2] {
+    [
+        ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+    ]
+}
+
+fn fee_percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    fees[(fees.len() - 1) * usize::from(percentile.min(100)) / 100]
+}
+
+/// Units consumed by `instructions`, simulated behind the budget instructions they will carry.
+async fn simulate_units(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    unit_price: u64,
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<u64> {
+    let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        MAX_COMPUTE_UNIT_LIMIT,
+    )];
+    if unit_price > 0 {
+        simulated.push(ComputeBudgetInstruction::set_compute_unit_price(unit_price));
+    }
+    simulated.extend_from_slice(instructions);
+    let blockhash = client.get_latest_blockhash().await?;
+    let transaction = sign_transaction(&simulated, payer, signers, blockhash, lookup_tables)?;
+    let config = RpcSimulateTransactionConfig {
+        commitment: Some(client.commitment()),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = client
+        .simulate_transaction_with_config(&transaction, config)
+        .await?
+        .value;
+    if let Some(err) = result.err {
+        bail!(
+            "transaction simulation failed: {err}\n{}",
+            result.logs.unwrap_or_default().join("\n")
+        );
+    }
+    result
+        .units_consumed
+        .context("simulation did not report the compute units consumed")
+}
+
+impl FromStr for PriorityFee {
+    type Err = anyhow::Error;
+
+    /// `none`, `auto` for the median recent fee, `auto:<percentile>` or a fixed price.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(PriorityFee::None),
+            "auto" => Ok(PriorityFee::Recent { percentile: 50 }),
+            other => {
+                if let Some(percentile) = other.strip_prefix("auto:") {
+                    let percentile = percentile
+                        .parse()
+                        .ok()
+                        .filter(|percentile| *percentile <= 100)
+                        .with_context(|| format!("invalid percentile `{percentile}`"))?;
+                    return Ok(PriorityFee::Recent { percentile });
+                }
+                match other.parse() {
+                    Ok(price) => Ok(PriorityFee::Fixed(price)),
+                    Err(_) => bail!(
+                        "unknown priority fee `{other}`, expected none, auto, auto:<percentile> \
+                         or micro-lamports per compute unit"
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityFee::None => f.write_str("none"),
+            PriorityFee::Fixed(price) => write!(f, "{price}"),
+            PriorityFee::Recent { percentile } => write!(f, "auto:{percentile}"),
+        }
+    }
+}
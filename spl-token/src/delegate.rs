@@ -0,0 +1,109 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_token_2022_interface::{
+    instruction::{
+        approve as approve_instruction, approve_checked as approve_checked_instruction,
+        revoke as revoke_instruction,
+    },
+    state::Account,
+};
+
+use crate::{
+    account::get_token_account,
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Result of an approve or revoke, with the token account read back afterwards.
+///
+/// `account.delegate` and `account.delegated_amount` hold the new allowance.
+#[derive(Debug)]
+pub struct Delegation {
+    pub signature: Signature,
+    pub account: Account,
+}
+
+/// Let `delegate` transfer or burn up to `amount` base units from `account`.
+///
+/// `owner` must own the account and also pays the fee. Any previous delegate is replaced.
+pub async fn approve(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome<Delegation>> {
+    let instruction = approve_instruction(
+        &program.id(),
+        account,
+        delegate,
+        &owner.pubkey(),
+        &[&owner.pubkey()],
+        amount,
+    )?;
+    send_delegation(client, owner, instruction, account, options).await
+}
+
+/// Like [`approve`], but the program also checks `mint` and `decimals` against the account.
+#[allow(clippy::too_many_arguments)]
+pub async fn approve_checked(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    mint: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<Delegation>> {
+    let instruction = approve_checked_instruction(
+        &program.id(),
+        account,
+        mint,
+        delegate,
+        &owner.pubkey(),
+        &[&owner.pubkey()],
+        amount,
+        decimals,
+    )?;
+    send_delegation(client, owner, instruction, account, options).await
+}
+
+/// Remove the delegate of `account`, if any.
+pub async fn revoke(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Delegation>> {
+    let instruction =
+        revoke_instruction(&program.id(), account, &owner.pubkey(), &[&owner.pubkey()])?;
+    send_delegation(client, owner, instruction, account, options).await
+}
+
+async fn send_delegation(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    instruction: Instruction,
+    account: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Delegation>> {
+    send_instructions(client, &[instruction], &owner.pubkey(), &[owner], options)
+        .await?
+        .and_then(|signature| async move {
+            Ok(Delegation {
+                signature,
+                account: get_token_account(client, account).await?,
+            })
+        })
+        .await
+}
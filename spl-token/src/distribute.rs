@@ -0,0 +1,682 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use solana_address_lookup_table_interface::state::LOOKUP_TABLE_MAX_ADDRESSES;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::{AddressLookupTableAccount, Message, v0},
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::instruction::transfer_checked;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, mem,
+    path::Path,
+};
+
+use crate::{
+    account::get_token_account,
+    amount::ui_amount_to_amount,
+    compute_budget::reserved_instructions,
+    lookup_table::{
+        LookupTable, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+        get_lookup_table,
+    },
+    mint::get_mint,
+    nonce::DurableNonce,
+    program::TokenProgram,
+    transaction::{
+        MAX_SEND_ATTEMPTS, RecentBlockhash, SendMode, SendOptions, SendOutcome, durable_nonce,
+        nonce_signers, prepare_instructions, send_instructions, send_until_expired,
+        sign_transaction, wait_until_expired,
+    },
+};
+
+/// Largest serialized transaction the cluster accepts.
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Accounts a transaction can lock, those loaded from lookup tables included.
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+/// Instructions a transaction may run, those invoked by programs included.
+pub const MAX_INSTRUCTION_TRACE_LENGTH: usize = 64;
+
+/// Instructions an ATA creation runs for a missing account: itself and the four it invokes.
+const CREATE_ATA_TRACE_LENGTH: usize = 5;
+
+/// Rows sharing a lookup table, which holds at most the recipient and ATA of each next to the
+/// source and the mint.
+pub const ROWS_PER_LOOKUP_TABLE: usize = (LOOKUP_TABLE_MAX_ADDRESSES - 2) / 2;
+
+/// Accounts per `getMultipleAccounts` call, the RPC limit.
+const LOOKUP_BATCH_SIZE: usize = 100;
+
+/// One row of a distribution: a wallet and the base units it receives in its ATA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recipient {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Result of [`distribute`].
+#[derive(Debug, Default)]
+pub struct Distribution {
+    /// Every transaction sent, lookup table upkeep included.
+    pub signatures: Vec<Signature>,
+    /// Rows delivered by this run.
+    pub sent: usize,
+    /// Rows an earlier run had already delivered.
+    pub resumed: usize,
+    /// ATAs created for recipients that had none.
+    pub created_accounts: usize,
+    /// Lookup tables this run finished with. They are deactivated, and can be closed to
+    /// recover their rent once they have cooled down.
+    pub lookup_tables: Vec<Pubkey>,
+}
+
+/// Progress of a distribution, rewritten around every transaction.
+#[derive(Debug, Serialize, Deserialize)]
+struct DistributionState {
+    mint: String,
+    rows: Vec<RowState>,
+    /// A signed batch that may or may not have landed, settled before anything else is sent.
+    pending: Option<PendingBatch>,
+    /// Lookup table of each chunk of [`ROWS_PER_LOOKUP_TABLE`] rows that got one, until it is
+    /// deactivated.
+    #[serde(default)]
+    lookup_tables: BTreeMap<usize, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RowState {
+    recipient: String,
+    amount: u64,
+    /// Transaction that delivered the row.
+    signature: Option<String>,
+}
+
+/// The instructions delivering one row.
+struct PackedRow {
+    row: usize,
+    instructions: Vec<Instruction>,
+    /// Instructions they run, invoked ones included.
+    trace_length: usize,
+    /// Accounts they reference that a lookup table can hold.
+    addresses: Vec<Pubkey>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingBatch {
+    signature: String,
+    /// The recent blockhash the batch was signed with, or the nonce stored in `nonce_account`.
+    blockhash: String,
+    last_valid_block_height: u64,
+    rows: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce_account: Option<String>,
+}
+
+impl DistributionState {
+    fn new(mint: &Pubkey, recipients: &[Recipient]) -> Self {
+        Self {
+            mint: mint.to_string(),
+            rows: recipients
+                .iter()
+                .map(|recipient| RowState {
+                    recipient: recipient.owner.to_string(),
+                    amount: recipient.amount,
+                    signature: None,
+                })
+                .collect(),
+            pending: None,
+            lookup_tables: BTreeMap::new(),
+        }
+    }
+
+    /// Read the state of an earlier run, or start a new one when `path` does not exist.
+    fn load(path: &Path, mint: &Pubkey, recipients: &[Recipient]) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::new(mint, recipients));
+        }
+        let state: Self = serde_json::from_str(&fs::read_to_string(path)?)
+            .with_context(|| format!("failed to parse state file {}", path.display()))?;
+        let same_rows = state.rows.len() == recipients.len()
+            && state.rows.iter().zip(recipients).all(|(row, recipient)| {
+                row.recipient == recipient.owner.to_string() && row.amount == recipient.amount
+            });
+        if state.mint != mint.to_string() || !same_rows {
+            bail!(
+                "state file {} belongs to a different mint or recipient list, remove it to start over",
+                path.display()
+            );
+        }
+        Ok(state)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        //Write then rename, so an interrupted run never leaves a truncated file behind
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to write state file {}", path.display()))
+    }
+
+    fn delivered(&mut self, rows: &[usize], signature: &Signature) {
+        for &row in rows {
+            self.rows[row].signature = Some(signature.to_string());
+        }
+    }
+}
+
+/// Parse `recipient,amount` lines, amounts in tokens such as `1.5`.
+///
+/// A leading `recipient,amount` header, blank lines and `#` comments are skipped.
+pub fn parse_recipients(csv: &str, decimals: u8) -> Result<Vec<Recipient>> {
+    let mut recipients = Vec::new();
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let context = || format!("line {}: `{line}`", index + 1);
+        let (owner, amount) = line
+            .split_once(',')
+            .with_context(|| format!("{}: expected `recipient,amount`", context()))?;
+        let (owner, amount) = (owner.trim(), amount.trim());
+        if recipients.is_empty() && owner == "recipient" {
+            continue;
+        }
+        let owner = owner
+            .parse()
+            .with_context(|| format!("{}: invalid recipient", context()))?;
+        let amount = ui_amount_to_amount(amount, decimals).with_context(context)?;
+        if amount == 0 {
+            bail!("{}: amount must be positive", context());
+        }
+        recipients.push(Recipient { owner, amount });
+    }
+    Ok(recipients)
+}
+
+/// Send every recipient its amount from `owner`'s ATA, creating missing ATAs on the way.
+///
+/// Rows are packed into as few legacy transactions as fit. With `with_lookup_tables`, a chunk
+/// of [`ROWS_PER_LOOKUP_TABLE`] rows that packs into fewer transactions loading its accounts
+/// from a lookup table gets one, and goes out in v0 transactions. Creating, filling and
+/// deactivating the table takes more transactions than it saves, in exchange for fewer and
+/// larger batches.
+///
+/// Progress is kept in `state_path`, so running again with the same list resumes where an
+/// interrupted run stopped, with the lookup tables it made. Each table is deactivated once its
+/// chunk has no rows left. A batch whose outcome is unknown is looked up, and waited out until
+/// its blockhash expires if needed, before anything is resent. Unless sent, the remaining rows
+/// have to fit a single legacy transaction and the state is left alone.
+#[allow(clippy::too_many_arguments)]
+pub async fn distribute(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    recipients: &[Recipient],
+    state_path: &Path,
+    with_lookup_tables: bool,
+    options: &SendOptions,
+) -> Result<SendOutcome<Distribution>> {
+    //Batches landing behind the state's back would be sent again on the next run
+    if options.mode == SendMode::SignOnly {
+        bail!("a distribution sends its batches itself and cannot be exported for signing");
+    }
+    let mut state = DistributionState::load(state_path, mint, recipients)?;
+    if let Some(pending) = state.pending.take() {
+        if options.mode != SendMode::Send {
+            bail!("a batch of an earlier run is pending, it has to be settled by sending first");
+        }
+        settle_pending(client, &mut state, pending).await?;
+        state.save(state_path)?;
+    }
+
+    let remaining: Vec<usize> = (0..recipients.len())
+        .filter(|&row| state.rows[row].signature.is_none())
+        .collect();
+    let mut distribution = Distribution {
+        resumed: recipients.len() - remaining.len(),
+        ..Distribution::default()
+    };
+    if remaining.is_empty() {
+        if options.mode == SendMode::Send {
+            finish_lookup_tables(
+                client,
+                owner,
+                &mut state,
+                state_path,
+                &mut distribution,
+                options,
+            )
+            .await?;
+        }
+        return Ok(SendOutcome::Sent(distribution));
+    }
+
+    let decimals = get_mint(client, mint).await?.decimals;
+    let source = program.ata_address(&owner.pubkey(), mint);
+    let needed = remaining
+        .iter()
+        .try_fold(0u64, |total, &row| {
+            total.checked_add(recipients[row].amount)
+        })
+        .context("total amount overflows u64")?;
+    let balance = get_token_account(client, &source).await?.amount;
+    if balance < needed {
+        bail!("{source} holds {balance} but the remaining rows need {needed}");
+    }
+
+    let destinations: Vec<Pubkey> = remaining
+        .iter()
+        .map(|&row| program.ata_address(&recipients[row].owner, mint))
+        .collect();
+    let mut existing = existing_accounts(client, &destinations).await?;
+
+    let mut packed = Vec::new();
+    for (&row, destination) in remaining.iter().zip(&destinations) {
+        let recipient = &recipients[row];
+        let mut instructions = Vec::new();
+        let mut trace_length = 1;
+        let mut addresses = vec![*destination];
+        if existing.insert(*destination) {
+            instructions.push(create_associated_token_account_idempotent(
+                &owner.pubkey(),
+                &recipient.owner,
+                mint,
+                &program.id(),
+            ));
+            trace_length += CREATE_ATA_TRACE_LENGTH;
+            addresses.push(recipient.owner);
+            distribution.created_accounts += 1;
+        }
+        instructions.push(transfer_checked(
+            &program.id(),
+            &source,
+            mint,
+            destination,
+            &owner.pubkey(),
+            &[&owner.pubkey()],
+            recipient.amount,
+            decimals,
+        )?);
+        packed.push(PackedRow {
+            row,
+            instructions,
+            trace_length,
+            addresses,
+        });
+    }
+
+    //Distributions never write the nonce account, so every batch advances it
+    let nonce = durable_nonce(&[], options);
+    let chunks: Vec<&[PackedRow]> = packed
+        .chunk_by(|a, b| a.row / ROWS_PER_LOOKUP_TABLE == b.row / ROWS_PER_LOOKUP_TABLE)
+        .collect();
+    if options.mode != SendMode::Send {
+        let batches: Vec<_> = chunks
+            .iter()
+            .flat_map(|rows| pack(rows, &owner.pubkey(), &[], nonce))
+            .collect();
+        options.mode.ensure_single(batches.len())?;
+        let (instructions, _) = &batches[0];
+        let outcome =
+            send_instructions(client, instructions, &owner.pubkey(), &[owner], options).await?;
+        return Ok(outcome.map(|_| distribution));
+    }
+
+    for rows in chunks {
+        let mut lookup_tables = Vec::new();
+        let mut batches = pack(rows, &owner.pubkey(), &lookup_tables, nonce);
+        let chunk = rows[0].row / ROWS_PER_LOOKUP_TABLE;
+        //A table an earlier run made is paid for already
+        if batches.len() > 1 && (with_lookup_tables || state.lookup_tables.contains_key(&chunk)) {
+            let mut addresses = vec![source, *mint];
+            let mut seen: HashSet<Pubkey> = addresses.iter().copied().collect();
+            for row in rows {
+                addresses.extend(
+                    row.addresses
+                        .iter()
+                        .filter(|address| seen.insert(**address)),
+                );
+            }
+            let table = chunk_lookup_table(
+                client,
+                owner,
+                &mut state,
+                state_path,
+                chunk,
+                rows,
+                &addresses,
+                batches.len(),
+                &mut distribution,
+                options,
+            )
+            .await?;
+            if let Some(table) = table {
+                lookup_tables.push(table.account());
+                batches = pack(rows, &owner.pubkey(), &lookup_tables, nonce);
+            }
+        }
+
+        for (instructions, rows) in batches {
+            let signature = send_batch(
+                client,
+                owner,
+                &mut state,
+                state_path,
+                &instructions,
+                &rows,
+                &lookup_tables,
+                options,
+            )
+            .await?;
+            distribution.signatures.push(signature);
+            distribution.sent += rows.len();
+        }
+        finish_lookup_tables(
+            client,
+            owner,
+            &mut state,
+            state_path,
+            &mut distribution,
+            options,
+        )
+        .await?;
+    }
+
+    Ok(SendOutcome::Sent(distribution))
+}
+
+/// Group rows into transactions as large as fit, loading accounts from `lookup_tables`.
+///
+/// Room is left for advancing `nonce` when the batches are signed with it.
+fn pack(
+    rows: &[PackedRow],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+    nonce: Option<&DurableNonce>,
+) -> Vec<(Vec<Instruction>, Vec<usize>)> {
+    let reserved = reserved(nonce);
+    let mut batches = Vec::new();
+    let mut instructions = Vec::new();
+    let mut batch = Vec::new();
+    let mut trace_length = reserved.len();
+    for row in rows {
+        let candidate = [instructions.as_slice(), &row.instructions].concat();
+        if !batch.is_empty()
+            && (trace_length + row.trace_length > MAX_INSTRUCTION_TRACE_LENGTH
+                || !fits(&reserved, &candidate, payer, lookup_tables))
+        {
+            batches.push((
+                mem::replace(&mut instructions, row.instructions.clone()),
+                mem::replace(&mut batch, vec![row.row]),
+            ));
+            trace_length = reserved.len() + row.trace_length;
+        } else {
+            instructions = candidate;
+            batch.push(row.row);
+            trace_length += row.trace_length;
+        }
+    }
+    if !batch.is_empty() {
+        batches.push((instructions, batch));
+    }
+    batches
+}
+
+/// The instructions sending puts in front of a batch, at their largest: advancing `nonce`,
+/// then the compute budget ones.
+fn reserved(nonce: Option<&DurableNonce>) -> Vec<Instruction> {
+    let advance = nonce.map(DurableNonce::advance_instruction);
+    [advance.as_slice(), &reserved_instructions()].concat()
+}
+
+/// Whether a transaction carrying `instructions` behind the `reserved` ones stays within the
+/// size and account limits.
+fn fits(
+    reserved: &[Instruction],
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> bool {
+    let instructions = [reserved, instructions].concat();
+    let (signatures, message_size, accounts) = if lookup_tables.is_empty() {
+        let message = Message::new(&instructions, Some(payer));
+        (
+            message.header.num_required_signatures,
+            message.serialize().len(),
+            message.account_keys.len(),
+        )
+    } else {
+        let Ok(message) =
+            v0::Message::try_compile(payer, &instructions, lookup_tables, Hash::default())
+        else {
+            return false;
+        };
+        let loaded: usize = message
+            .address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum();
+        (
+            message.header.num_required_signatures,
+            message.serialize().len(),
+            message.account_keys.len() + loaded,
+        )
+    };
+    //Compact length prefix (one byte below 128 signatures), then the signatures
+    let size = 1 + 64 * signatures as usize + message_size;
+    size <= MAX_TRANSACTION_SIZE && accounts <= MAX_TRANSACTION_ACCOUNTS
+}
+
+/// The lookup table of a chunk of `rows`, holding `addresses`, or `None` when the rows would
+/// take no fewer than their `legacy_batches` loading accounts from one.
+///
+/// A new table is recorded in the state before it is filled, so a resumed run completes it
+/// instead of paying for another one.
+#[allow(clippy::too_many_arguments)]
+async fn chunk_lookup_table(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    state: &mut DistributionState,
+    state_path: &Path,
+    chunk: usize,
+    rows: &[PackedRow],
+    addresses: &[Pubkey],
+    legacy_batches: usize,
+    distribution: &mut Distribution,
+    options: &SendOptions,
+) -> Result<Option<LookupTable>> {
+    let address = match state.lookup_tables.get(&chunk) {
+        Some(address) => address.parse()?,
+        None => {
+            //Sized with a stand-in key, the real one is only known once the table is created
+            let table = AddressLookupTableAccount {
+                key: Pubkey::default(),
+                addresses: addresses.to_vec(),
+            };
+            let batches = pack(rows, &owner.pubkey(), &[table], durable_nonce(&[], options)).len();
+            if batches >= legacy_batches {
+                return Ok(None);
+            }
+            let created = create_lookup_table(client, owner, &[], options)
+                .await?
+                .sent()?;
+            distribution.signatures.extend(created.signatures);
+            state
+                .lookup_tables
+                .insert(chunk, created.table.address.to_string());
+            state.save(state_path)?;
+            created.table.address
+        }
+    };
+    let signatures = extend_lookup_table(client, owner, &address, addresses, options)
+        .await?
+        .sent()?;
+    distribution.signatures.extend(signatures);
+    Ok(Some(get_lookup_table(client, &address).await?))
+}
+
+/// Deactivate the lookup tables of chunks with no rows left, and drop them from the state.
+///
+/// Tables of chunks an earlier run finished are included, whether it stopped before
+/// deactivating them or after.
+async fn finish_lookup_tables(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    state: &mut DistributionState,
+    state_path: &Path,
+    distribution: &mut Distribution,
+    options: &SendOptions,
+) -> Result<()> {
+    let finished: Vec<usize> = state
+        .lookup_tables
+        .keys()
+        .copied()
+        .filter(|&chunk| {
+            state
+                .rows
+                .iter()
+                .skip(chunk * ROWS_PER_LOOKUP_TABLE)
+                .take(ROWS_PER_LOOKUP_TABLE)
+                .all(|row| row.signature.is_some())
+        })
+        .collect();
+    for chunk in finished {
+        let address: Pubkey = state.lookup_tables[&chunk].parse()?;
+        if !get_lookup_table(client, &address).await?.is_deactivated() {
+            let signature = deactivate_lookup_table(client, owner, &address, options)
+                .await?
+                .sent()?;
+            distribution.signatures.push(signature);
+        }
+        distribution.lookup_tables.push(address);
+        state.lookup_tables.remove(&chunk);
+        state.save(state_path)?;
+    }
+    Ok(())
+}
+
+/// The subset of `addresses` that already exist.
+async fn existing_accounts(client: &RpcClient, addresses: &[Pubkey]) -> Result<HashSet<Pubkey>> {
+    let mut existing = HashSet::new();
+    for chunk in addresses.chunks(LOOKUP_BATCH_SIZE) {
+        let accounts = client.get_multiple_accounts(chunk).await?;
+        existing.extend(
+            chunk
+                .iter()
+                .zip(accounts)
+                .filter_map(|(address, account)| account.map(|_| *address)),
+        );
+    }
+    Ok(existing)
+}
+
+/// Sign one batch, record it as pending, then send it and record its rows as delivered.
+///
+/// A batch that expires unconfirmed is signed again with a new blockhash.
+#[allow(clippy::too_many_arguments)]
+async fn send_batch(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    state: &mut DistributionState,
+    state_path: &Path,
+    instructions: &[Instruction],
+    rows: &[usize],
+    lookup_tables: &[AddressLookupTableAccount],
+    options: &SendOptions,
+) -> Result<Signature> {
+    let nonce = durable_nonce(instructions, options);
+    let signers = nonce_signers(&[owner], nonce);
+    let instructions = prepare_instructions(
+        client,
+        instructions,
+        &owner.pubkey(),
+        &signers,
+        lookup_tables,
+        nonce,
+        options,
+    )
+    .await?;
+    for _ in 0..MAX_SEND_ATTEMPTS {
+        let blockhash = RecentBlockhash::fetch_for(client, nonce).await?;
+        let transaction = sign_transaction(
+            &instructions,
+            &owner.pubkey(),
+            &signers,
+            blockhash.blockhash,
+            lookup_tables,
+        )?;
+        let signature = transaction.signatures[0];
+        state.pending = Some(PendingBatch {
+            signature: signature.to_string(),
+            blockhash: blockhash.blockhash.to_string(),
+            last_valid_block_height: blockhash.last_valid_block_height,
+            rows: rows.to_vec(),
+            nonce_account: blockhash.nonce_account.map(|account| account.to_string()),
+        });
+        state.save(state_path)?;
+
+        match send_until_expired(client, &transaction, &blockhash).await {
+            Ok(Some(signature)) => {
+                state.pending = None;
+                state.delivered(rows, &signature);
+                state.save(state_path)?;
+                return Ok(signature);
+            }
+            Ok(None) => {
+                state.pending = None;
+                state.save(state_path)?;
+            }
+            Err(err) => {
+                //A transaction refused in preflight never lands, anything else is settled on resume
+                if let Some(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    data: RpcResponseErrorData::SendTransactionPreflightFailure(_),
+                    ..
+                })) = err.downcast_ref::<ClientError>().map(ClientError::kind)
+                {
+                    state.pending = None;
+                    state.save(state_path)?;
+                }
+                return Err(err)
+                    .with_context(|| format!("batch {signature} failed, run again to resume"));
+            }
+        }
+    }
+    bail!("batch expired unconfirmed {MAX_SEND_ATTEMPTS} times, run again to resume")
+}
+
+/// Find out whether a batch from an interrupted run landed, waiting for its blockhash to
+/// expire, or its nonce to advance, when the cluster has not seen it yet.
+async fn settle_pending(
+    client: &RpcClient,
+    state: &mut DistributionState,
+    pending: PendingBatch,
+) -> Result<()> {
+    let signature: Signature = pending.signature.parse()?;
+    let blockhash = RecentBlockhash {
+        blockhash: pending.blockhash.parse()?,
+        last_valid_block_height: pending.last_valid_block_height,
+        nonce_account: pending
+            .nonce_account
+            .as_deref()
+            .map(str::parse)
+            .transpose()?,
+    };
+    //A failed batch delivered nothing and its rows are sent again
+    if let Some(Ok(())) = wait_until_expired(client, &signature, &blockhash, None).await? {
+        state.delivered(&pending.rows, &signature);
+    }
+    Ok(())
+}
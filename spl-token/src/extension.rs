@@ -0,0 +1,191 @@
+use anyhow::{Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_token_2022_interface::{
+    extension::{
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions, default_account_state,
+        interest_bearing_mint, metadata_pointer, transfer_fee,
+    },
+    instruction::{
+        initialize_mint_close_authority, initialize_non_transferable_mint,
+        initialize_permanent_delegate,
+    },
+    state::{AccountState, Mint},
+};
+
+use crate::{metadata::Metadata, program::TokenProgram};
+
+/// Token-2022 extensions to configure when creating a mint.
+#[derive(Debug, Clone, Default)]
+pub struct MintExtensions {
+    pub transfer_fee: Option<TransferFee>,
+    pub interest_rate: Option<InterestRate>,
+    pub non_transferable: bool,
+    pub permanent_delegate: Option<Pubkey>,
+    pub close_authority: Option<Pubkey>,
+    pub default_account_state: Option<AccountState>,
+    pub metadata_pointer: Option<MetadataPointer>,
+    /// Written after the mint is initialized, needs a metadata pointer to the mint itself.
+    pub metadata: Option<Metadata>,
+}
+
+/// Fee withheld on every transfer, in basis points of the amount and capped at `maximum_fee`.
+#[derive(Debug, Clone)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+    pub config_authority: Option<Pubkey>,
+    pub withdraw_authority: Option<Pubkey>,
+}
+
+/// Continuously compounding interest, in basis points per year, applied to displayed amounts.
+#[derive(Debug, Clone)]
+pub struct InterestRate {
+    pub rate: i16,
+    pub authority: Option<Pubkey>,
+}
+
+/// Where the mint's metadata lives, usually the mint itself.
+#[derive(Debug, Clone)]
+pub struct MetadataPointer {
+    pub authority: Option<Pubkey>,
+    pub metadata_address: Option<Pubkey>,
+}
+
+impl MintExtensions {
+    pub fn is_empty(&self) -> bool {
+        self.extension_types().is_empty() && self.metadata.is_none()
+    }
+
+    /// The fixed-size extension types enabled, used to size the mint account.
+    ///
+    /// Token metadata is not listed, the program grows the account when it is written.
+    pub fn extension_types(&self) -> Vec<ExtensionType> {
+        let mut types = Vec::new();
+        if self.transfer_fee.is_some() {
+            types.push(ExtensionType::TransferFeeConfig);
+        }
+        if self.interest_rate.is_some() {
+            types.push(ExtensionType::InterestBearingConfig);
+        }
+        if self.non_transferable {
+            types.push(ExtensionType::NonTransferable);
+        }
+        if self.permanent_delegate.is_some() {
+            types.push(ExtensionType::PermanentDelegate);
+        }
+        if self.close_authority.is_some() {
+            types.push(ExtensionType::MintCloseAuthority);
+        }
+        if self.default_account_state.is_some() {
+            types.push(ExtensionType::DefaultAccountState);
+        }
+        if self.metadata_pointer.is_some() {
+            types.push(ExtensionType::MetadataPointer);
+        }
+        types
+    }
+
+    /// Size of a mint account carrying these extensions.
+    pub fn mint_len(&self) -> Result<usize> {
+        Ok(ExtensionType::try_calculate_account_len::<Mint>(
+            &self.extension_types(),
+        )?)
+    }
+
+    /// Size of the mint once its metadata, if any, has been written. Rent is paid on this.
+    pub fn funded_len(&self, mint: &Pubkey, mint_authority: &Pubkey) -> Result<usize> {
+        let metadata_len = match &self.metadata {
+            Some(metadata) => metadata.extension_len(mint, mint_authority)?,
+            None => 0,
+        };
+        Ok(self.mint_len()? + metadata_len)
+    }
+
+    /// Extension initialization instructions, which must run after the account is allocated
+    /// and before `initialize_mint`.
+    pub fn instructions(&self, program: TokenProgram, mint: &Pubkey) -> Result<Vec<Instruction>> {
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+        if program != TokenProgram::Token2022 {
+            bail!("mint extensions require the token-2022 program");
+        }
+        if self.metadata.is_some()
+            && self
+                .metadata_pointer
+                .as_ref()
+                .and_then(|p| p.metadata_address)
+                != Some(*mint)
+        {
+            bail!("token metadata is stored on the mint and needs a metadata pointer to it");
+        }
+        let program_id = program.id();
+        let mut instructions = Vec::new();
+        if let Some(fee) = &self.transfer_fee {
+            instructions.push(transfer_fee::instruction::initialize_transfer_fee_config(
+                &program_id,
+                mint,
+                fee.config_authority.as_ref(),
+                fee.withdraw_authority.as_ref(),
+                fee.basis_points,
+                fee.maximum_fee,
+            )?);
+        }
+        if let Some(interest) = &self.interest_rate {
+            instructions.push(interest_bearing_mint::instruction::initialize(
+                &program_id,
+                mint,
+                interest.authority,
+                interest.rate,
+            )?);
+        }
+        if self.non_transferable {
+            instructions.push(initialize_non_transferable_mint(&program_id, mint)?);
+        }
+        if let Some(delegate) = &self.permanent_delegate {
+            instructions.push(initialize_permanent_delegate(&program_id, mint, delegate)?);
+        }
+        if let Some(authority) = &self.close_authority {
+            instructions.push(initialize_mint_close_authority(
+                &program_id,
+                mint,
+                Some(authority),
+            )?);
+        }
+        if let Some(state) = &self.default_account_state {
+            instructions.push(
+                default_account_state::instruction::initialize_default_account_state(
+                    &program_id,
+                    mint,
+                    state,
+                )?,
+            );
+        }
+        if let Some(pointer) = &self.metadata_pointer {
+            instructions.push(metadata_pointer::instruction::initialize(
+                &program_id,
+                mint,
+                pointer.authority,
+                pointer.metadata_address,
+            )?);
+        }
+        Ok(instructions)
+    }
+}
+
+/// Extension types present on a mint, empty for the original token program.
+pub async fn get_mint_extensions(client: &RpcClient, mint: &Pubkey) -> Result<Vec<ExtensionType>> {
+    let account = client.get_account(mint).await?;
+    TokenProgram::from_owner(&account.owner)?;
+    Ok(StateWithExtensions::<Mint>::unpack(&account.data)?.get_extension_types()?)
+}
+
+/// Parse `initialized` or `frozen` for the default account state extension.
+pub fn parse_account_state(s: &str) -> Result<AccountState> {
+    match s {
+        "initialized" => Ok(AccountState::Initialized),
+        "frozen" => Ok(AccountState::Frozen),
+        other => bail!("unknown account state `{other}`, expected initialized or frozen"),
+    }
+}
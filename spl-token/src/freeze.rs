@@ -0,0 +1,208 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_token_2022_interface::{
+    instruction::{freeze_account as freeze_instruction, thaw_account as thaw_instruction},
+    state::{Account, AccountState},
+};
+use std::{error::Error, fmt};
+
+use crate::{
+    account::{get_token_account, get_token_accounts_by_mint},
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Freeze instructions packed into one transaction by [`freeze_all`].
+pub const FREEZE_BATCH_SIZE: usize = 20;
+
+/// A token account whose state was changed by a freeze or thaw.
+#[derive(Debug)]
+pub struct StateChange {
+    pub signature: Signature,
+    pub address: Pubkey,
+    pub before: AccountState,
+    pub account: Account,
+}
+
+/// A [`freeze_all`] stopped by a failing batch, with the accounts earlier batches froze.
+#[derive(Debug)]
+pub struct FreezeAllError {
+    pub frozen: Vec<StateChange>,
+    pub source: anyhow::Error,
+}
+
+impl fmt::Display for FreezeAllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "froze {} accounts before a batch failed",
+            self.frozen.len()
+        )
+    }
+}
+
+impl Error for FreezeAllError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Freeze `account` so neither its owner nor delegate can move or burn its tokens.
+///
+/// `freeze_authority` must be the mint's freeze authority and also pays the fee.
+pub async fn freeze_account(
+    client: &RpcClient,
+    freeze_authority: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    mint: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<StateChange>> {
+    let before = get_token_account(client, account).await?.state;
+    let instruction = freeze_instruction(
+        &program.id(),
+        account,
+        mint,
+        &freeze_authority.pubkey(),
+        &[&freeze_authority.pubkey()],
+    )?;
+
+    send_instructions(
+        client,
+        &[instruction],
+        &freeze_authority.pubkey(),
+        &[freeze_authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(StateChange {
+            signature,
+            address: *account,
+            before,
+            account: get_token_account(client, account).await?,
+        })
+    })
+    .await
+}
+
+/// Thaw a frozen `account`, signed by the mint's freeze authority.
+pub async fn thaw_account(
+    client: &RpcClient,
+    freeze_authority: &dyn Signer,
+    program: TokenProgram,
+    account: &Pubkey,
+    mint: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<StateChange>> {
+    let before = get_token_account(client, account).await?.state;
+    let instruction = thaw_instruction(
+        &program.id(),
+        account,
+        mint,
+        &freeze_authority.pubkey(),
+        &[&freeze_authority.pubkey()],
+    )?;
+
+    send_instructions(
+        client,
+        &[instruction],
+        &freeze_authority.pubkey(),
+        &[freeze_authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(StateChange {
+            signature,
+            address: *account,
+            before,
+            account: get_token_account(client, account).await?,
+        })
+    })
+    .await
+}
+
+/// Freeze every token account of `mint` that is not frozen yet.
+///
+/// Holders are found with `getProgramAccounts` and frozen in batches of
+/// [`FREEZE_BATCH_SIZE`]. A failing batch stops the run with a [`FreezeAllError`] listing the
+/// accounts earlier batches froze. Unless sent, the holders have to fit a single batch.
+pub async fn freeze_all(
+    client: &RpcClient,
+    freeze_authority: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Vec<StateChange>>> {
+    let holders: Vec<(Pubkey, Account)> = get_token_accounts_by_mint(client, program, mint)
+        .await?
+        .into_iter()
+        .filter(|(_, account)| account.state != AccountState::Frozen)
+        .collect();
+
+    let mut changes = Vec::with_capacity(holders.len());
+    let batches = holders.chunks(FREEZE_BATCH_SIZE);
+    options.mode.ensure_single(batches.len())?;
+    for batch in batches {
+        let instructions = batch
+            .iter()
+            .map(|(address, _)| {
+                freeze_instruction(
+                    &program.id(),
+                    address,
+                    mint,
+                    &freeze_authority.pubkey(),
+                    &[&freeze_authority.pubkey()],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let outcome = send_instructions(
+            client,
+            &instructions,
+            &freeze_authority.pubkey(),
+            &[freeze_authority],
+            options,
+        )
+        .await;
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => return Err(partial(changes, err)),
+        };
+        //Not sent, so this is the only batch
+        let SendOutcome::Sent(signature) = outcome else {
+            return Ok(outcome.map(|_| changes));
+        };
+
+        for (address, account) in batch {
+            let after = match get_token_account(client, address).await {
+                Ok(after) => after,
+                Err(err) => return Err(partial(changes, err)),
+            };
+            changes.push(StateChange {
+                signature,
+                address: *address,
+                before: account.state,
+                account: after,
+            });
+        }
+    }
+    Ok(SendOutcome::Sent(changes))
+}
+
+/// `err`, along with the accounts frozen before it when there are any.
+fn partial(frozen: Vec<StateChange>, err: anyhow::Error) -> anyhow::Error {
+    if frozen.is_empty() {
+        return err;
+    }
+    FreezeAllError {
+        frozen,
+        source: err,
+    }
+    .into()
+}
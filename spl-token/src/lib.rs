@@ -0,0 +1,72 @@
+//! Reusable building blocks behind the `spl-token` binaries.
+//!
+//! Every flow that used to live inline in a `main` (connecting to the rpc,
+//! loading the wallet, airdropping, creating mints and token accounts, minting
+//! and transferring) is exposed here as an async function returning a typed
+//! result, so other services can call them directly.
+
+pub mod account;
+pub mod airdrop;
+pub mod amount;
+pub mod authority;
+pub mod burn;
+pub mod close;
+pub mod cluster;
+pub mod compute_budget;
+pub mod delegate;
+pub mod distribute;
+pub mod extension;
+pub mod freeze;
+pub mod lookup_table;
+pub mod metadata;
+pub mod mint;
+pub mod multisig;
+pub mod native;
+pub mod nonce;
+pub mod offline;
+pub mod program;
+pub mod signer;
+pub mod simulate;
+pub mod transaction;
+pub mod transfer;
+
+pub use account::{
+    Ata, CreatedAta, CreatedTokenAccount, OwnedTokenAccount, create_ata, create_token_account,
+    get_or_create_ata, get_token_account, get_token_accounts_by_mint, get_token_accounts_by_owner,
+};
+pub use airdrop::{Funded, FundingOptions, airdrop, ensure_funded};
+pub use amount::{amount_to_ui_amount, parse_ui_amount, ui_amount_to_amount};
+pub use authority::{AuthorityChange, get_authority, set_authority};
+pub use burn::{Burned, burn, burn_checked};
+pub use close::{Closed, Sweep, close_account, sweep_empty_accounts};
+pub use cluster::{Cluster, ClusterConfig, ClusterSelection};
+pub use compute_budget::{ComputeBudget, PriorityFee};
+pub use delegate::{Delegation, approve, approve_checked, revoke};
+pub use distribute::{Distribution, Recipient, distribute, parse_recipients};
+pub use extension::{
+    InterestRate, MetadataPointer, MintExtensions, TransferFee, get_mint_extensions,
+};
+pub use freeze::{FreezeAllError, StateChange, freeze_account, freeze_all, thaw_account};
+pub use lookup_table::{
+    CreatedLookupTable, LookupTable, close_lookup_table, create_lookup_table,
+    deactivate_lookup_table, extend_lookup_table, get_lookup_table,
+};
+pub use metadata::{Metadata, UpdatedMetadata, get_metadata, update_metadata};
+pub use mint::{
+    CreatedMint, MintedTokens, create_mint, create_mint_with_extensions, get_mint, mint_to,
+    mint_to_with_signers,
+};
+pub use multisig::{CreatedMultisig, create_multisig, get_multisig};
+pub use native::{Wrapped, unwrap_sol, wrap_sol};
+pub use nonce::{
+    CreatedNonceAccount, DurableNonce, NonceAccount, advance_nonce, authorize_nonce_account,
+    create_nonce_account, get_nonce_account, withdraw_from_nonce_account,
+};
+pub use offline::{ExportFormat, ExportedTransaction, broadcast};
+pub use program::TokenProgram;
+pub use signer::{KeypairSource, load_default_keypair, load_keypair};
+pub use simulate::{PostState, Simulation, simulate};
+pub use transaction::{SendMode, SendOptions, SendOutcome};
+pub use transfer::{
+    Transfer, transfer_checked, transfer_checked_to_owner, transfer_checked_with_signers,
+};
@@ -0,0 +1,233 @@
+use anyhow::{Result, bail};
+use solana_address_lookup_table_interface::{
+    instruction::{
+        close_lookup_table as close_lookup_table_instruction,
+        create_lookup_table as create_lookup_table_instruction,
+        deactivate_lookup_table as deactivate_lookup_table_instruction,
+        extend_lookup_table as extend_lookup_table_instruction,
+    },
+    program,
+    state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES, LookupTableMeta},
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{
+    clock::Slot,
+    message::AddressLookupTableAccount,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use std::{collections::HashSet, time::Duration};
+use tokio::time::sleep;
+
+use crate::transaction::{SendOptions, SendOutcome, send_instructions};
+
+/// Addresses added per extend transaction, leaving room for the compute budget instructions.
+pub const EXTEND_BATCH_SIZE: usize = 25;
+
+/// How often the slot is checked while waiting for new addresses to become usable.
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// An address lookup table and the addresses it holds.
+#[derive(Debug, Clone)]
+pub struct LookupTable {
+    pub address: Pubkey,
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// The table as v0 transactions compile against it.
+    pub fn account(&self) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses.clone(),
+        }
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.meta.deactivation_slot != Slot::MAX
+    }
+}
+
+/// A freshly created lookup table, with the transactions that created and filled it.
+#[derive(Debug)]
+pub struct CreatedLookupTable {
+    pub signatures: Vec<Signature>,
+    pub table: LookupTable,
+}
+
+/// Fetch and deserialize an address lookup table.
+pub async fn get_lookup_table(client: &RpcClient, address: &Pubkey) -> Result<LookupTable> {
+    let account = client.get_account(address).await?;
+    if account.owner != program::id() {
+        bail!("{address} is not an address lookup table");
+    }
+    let table = AddressLookupTable::deserialize(&account.data)?;
+    Ok(LookupTable {
+        address: *address,
+        meta: table.meta,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Create a lookup table owned by `authority`, which also pays for it, holding `addresses`.
+///
+/// Returns once the addresses can be used. Unless sent, the addresses have to fit the
+/// transaction creating the table.
+pub async fn create_lookup_table(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    addresses: &[Pubkey],
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedLookupTable>> {
+    if addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        bail!("a lookup table holds at most {LOOKUP_TABLE_MAX_ADDRESSES} addresses");
+    }
+    //The address is derived from a slot the cluster still has the hash of
+    let recent_slot = client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await?;
+    let (create_instruction, address) =
+        create_lookup_table_instruction(authority.pubkey(), authority.pubkey(), recent_slot);
+    let (first, rest) = addresses.split_at(addresses.len().min(EXTEND_BATCH_SIZE));
+    options
+        .mode
+        .ensure_single(1 + rest.len().div_ceil(EXTEND_BATCH_SIZE))?;
+    let mut instructions = vec![create_instruction];
+    if !first.is_empty() {
+        instructions.push(extend_lookup_table_instruction(
+            address,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            first.to_vec(),
+        ));
+    }
+    send_instructions(
+        client,
+        &instructions,
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        let mut signatures = vec![signature];
+        signatures.extend(
+            extend_lookup_table(client, authority, &address, rest, options)
+                .await?
+                .sent()?,
+        );
+
+        let table = get_lookup_table(client, &address).await?;
+        wait_until_usable(client, &table).await?;
+        Ok(CreatedLookupTable { signatures, table })
+    })
+    .await
+}
+
+/// Add the `addresses` a table does not hold yet, returning once they can be used. Unless sent,
+/// the missing addresses have to fit a single transaction.
+pub async fn extend_lookup_table(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    table: &Pubkey,
+    addresses: &[Pubkey],
+    options: &SendOptions,
+) -> Result<SendOutcome<Vec<Signature>>> {
+    let current = get_lookup_table(client, table).await?;
+    let mut held: HashSet<Pubkey> = current.addresses.iter().copied().collect();
+    let missing: Vec<Pubkey> = addresses
+        .iter()
+        .filter(|address| held.insert(**address))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        return Ok(SendOutcome::Sent(Vec::new()));
+    }
+    if held.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        bail!(
+            "{table} holds {} addresses, {} more would exceed {LOOKUP_TABLE_MAX_ADDRESSES}",
+            current.addresses.len(),
+            missing.len()
+        );
+    }
+
+    let chunks = missing.chunks(EXTEND_BATCH_SIZE);
+    options.mode.ensure_single(chunks.len())?;
+    let mut signatures = Vec::new();
+    for chunk in chunks {
+        let instruction = extend_lookup_table_instruction(
+            *table,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            chunk.to_vec(),
+        );
+        let outcome = send_instructions(
+            client,
+            &[instruction],
+            &authority.pubkey(),
+            &[authority],
+            options,
+        )
+        .await?;
+        //Not sent, so this is the only chunk
+        let SendOutcome::Sent(signature) = outcome else {
+            return Ok(outcome.map(|_| signatures));
+        };
+        signatures.push(signature);
+    }
+    wait_until_usable(client, &get_lookup_table(client, table).await?).await?;
+    Ok(SendOutcome::Sent(signatures))
+}
+
+/// Stop a table from being extended or used, so it can be closed once it cools down.
+pub async fn deactivate_lookup_table(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    table: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    let instruction = deactivate_lookup_table_instruction(*table, authority.pubkey());
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await
+}
+
+/// Close a deactivated table, sending its rent to `recipient`.
+///
+/// The program refuses until the deactivation slot has aged out of the recent slots, about
+/// 512 slots after [`deactivate_lookup_table`].
+pub async fn close_lookup_table(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    table: &Pubkey,
+    recipient: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    if !get_lookup_table(client, table).await?.is_deactivated() {
+        bail!("{table} must be deactivated before it can be closed");
+    }
+    let instruction = close_lookup_table_instruction(*table, authority.pubkey(), *recipient);
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await
+}
+
+/// Addresses added to a table can only be looked up from the slot after they were added.
+async fn wait_until_usable(client: &RpcClient, table: &LookupTable) -> Result<()> {
+    while client.get_slot().await? <= table.meta.last_extended_slot {
+        sleep(SLOT_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
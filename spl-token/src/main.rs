@@ -1 +1,1500 @@
-fn main() {}
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{
+    program_option::COption,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token_2022_interface::{
+    instruction::AuthorityType,
+    native_mint::DECIMALS as NATIVE_DECIMALS,
+    state::{Account, AccountState, Mint},
+};
+use spl_token_metadata_interface::state::Field;
+use spl_token_tools::{
+    Cluster, ClusterConfig, ClusterSelection, ComputeBudget, DurableNonce, ExportFormat,
+    ExportedTransaction, FreezeAllError, FundingOptions, InterestRate, KeypairSource, Metadata,
+    MetadataPointer, MintExtensions, NonceAccount, PostState, PriorityFee, SendMode, SendOptions,
+    SendOutcome, Simulation, StateChange, TokenProgram, TransferFee, advance_nonce, airdrop,
+    airdrop::FUNDER_ENV,
+    amount_to_ui_amount, approve_checked,
+    authority::parse_authority_type,
+    authorize_nonce_account, broadcast, burn_checked, close_account, close_lookup_table,
+    cluster::{CLUSTER_ENV, COMMITMENT_ENV, CONFIG_ENV, PROFILE_ENV, WS_URL_ENV, parse_commitment},
+    compute_budget::PRIORITY_FEE_ENV,
+    create_lookup_table, create_mint_with_extensions, create_multisig, create_nonce_account,
+    create_token_account, deactivate_lookup_table, distribute, ensure_funded, extend_lookup_table,
+    extension::parse_account_state,
+    freeze_account, freeze_all, get_lookup_table, get_metadata, get_mint, get_mint_extensions,
+    get_nonce_account, get_or_create_ata, get_token_account, load_default_keypair,
+    metadata::parse_field,
+    mint_to_with_signers, parse_recipients, revoke, set_authority,
+    signer::KEYPAIR_ENV,
+    sweep_empty_accounts, thaw_account, transfer_checked_to_owner, ui_amount_to_amount, unwrap_sol,
+    update_metadata, withdraw_from_nonce_account, wrap_sol,
+};
+use std::{
+    future::Future,
+    io::{self, Read},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Create mints and token accounts, mint and transfer SPL tokens.
+#[derive(Parser)]
+#[command(name = "spl-token", version)]
+struct Cli {
+    /// Cluster to talk to: localnet, devnet, testnet, mainnet or an rpc url [default: devnet]
+    #[arg(short = 'u', long = "url", global = true, env = CLUSTER_ENV)]
+    cluster: Option<Cluster>,
+
+    /// Websocket url (derived from the rpc url when not given)
+    #[arg(long, global = true, env = WS_URL_ENV)]
+    ws_url: Option<String>,
+
+    /// Default commitment: processed, confirmed or finalized [default: confirmed]
+    #[arg(long, global = true, env = COMMITMENT_ENV, value_parser = parse_commitment)]
+    commitment: Option<CommitmentConfig>,
+
+    /// Named profile from the profile file
+    #[arg(long, global = true, env = PROFILE_ENV)]
+    profile: Option<String>,
+
+    /// TOML profile file [default: ./spl-token.toml when present]
+    #[arg(long, global = true, env = CONFIG_ENV)]
+    config: Option<PathBuf>,
+
+    /// Fee payer keypair: a file path, `config`, `env:<VAR>` or `mnemonic:<VAR>[:<path>]`
+    /// [default: WALLET_PATH, then the Solana CLI default keypair]
+    #[arg(short, long, global = true, env = KEYPAIR_ENV)]
+    keypair: Option<KeypairSource>,
+
+    /// Wallet that tops up accounts when the faucet refuses an airdrop (same spec as --keypair)
+    #[arg(long, global = true, env = FUNDER_ENV)]
+    funder: Option<KeypairSource>,
+
+    /// Token program: token or token-2022 [default: the mint's owner, token for new mints]
+    #[arg(short, long, global = true)]
+    program: Option<TokenProgram>,
+
+    /// Compute unit price: none, auto (the median recent fee), auto:<percentile> or
+    /// micro-lamports per unit [default: auto]
+    #[arg(long, global = true, env = PRIORITY_FEE_ENV)]
+    priority_fee: Option<PriorityFee>,
+
+    /// Cap on the compute unit price, in micro-lamports
+    #[arg(long, global = true)]
+    max_priority_fee: Option<u64>,
+
+    /// Compute units requested above the simulated consumption, in percent [default: 20]
+    #[arg(long, global = true)]
+    compute_unit_margin: Option<u32>,
+
+    /// Send without simulating and setting a compute unit limit
+    #[arg(long, global = true, conflicts_with = "compute_unit_margin")]
+    no_compute_unit_limit: bool,
+
+    /// Simulate the transaction instead of sending it, printing its logs, compute units and
+    /// the token accounts it would leave behind. Commands needing several transactions refuse
+    #[arg(long, global = true)]
+    simulate: bool,
+
+    /// Sign with the nonce stored in this nonce account instead of a recent blockhash, so the
+    /// transaction stays valid until the nonce advances
+    #[arg(long, global = true)]
+    nonce: Option<Pubkey>,
+
+    /// Authority of the --nonce account (same spec as --keypair) [default: the fee payer]
+    #[arg(long, global = true, requires = "nonce")]
+    nonce_authority: Option<KeypairSource>,
+
+    /// Sign the transaction with the keypairs at hand and print it instead of sending it.
+    /// Signers given as `pubkey:<ADDRESS>` are left to the `sign` command. Commands needing
+    /// several transactions refuse
+    #[arg(long, global = true, conflicts_with = "simulate")]
+    sign_only: bool,
+
+    /// How transactions are printed by --sign-only and `sign`: base64 or json
+    #[arg(long, global = true, default_value_t = ExportFormat::Base64)]
+    export_format: ExportFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new mint with the fee payer as mint and freeze authority
+    CreateMint {
+        #[arg(short, long, default_value_t = 9)]
+        decimals: u8,
+        #[command(flatten)]
+        extensions: ExtensionArgs,
+    },
+    /// Create a keypair-based token account for a mint
+    CreateAccount {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Owner of the new account (defaults to the fee payer)
+        #[arg(short, long)]
+        owner: Option<Pubkey>,
+    },
+    /// Create the associated token account of an owner for a mint, unless it already exists
+    CreateAta {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Owner of the ATA (defaults to the fee payer)
+        #[arg(short, long)]
+        owner: Option<Pubkey>,
+    },
+    /// Create an m-of-n multisig usable as a mint authority or token account owner
+    CreateMultisig {
+        /// Number of signers required to approve
+        #[arg(short, long)]
+        m: u8,
+        /// Pubkeys of the signers
+        #[arg(required = true)]
+        signers: Vec<Pubkey>,
+    },
+    /// Mint tokens into a token account, the fee payer or --multisig must be the mint authority
+    MintTo {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Amount in tokens, e.g. 1.5
+        #[arg(short, long)]
+        amount: String,
+        /// Destination token account (defaults to the fee payer's ATA)
+        #[arg(short, long)]
+        to: Option<Pubkey>,
+        #[command(flatten)]
+        multisig: MultisigArgs,
+    },
+    /// Transfer tokens from the fee payer's ATA to the recipient's ATA
+    Transfer {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Owner of the destination ATA, created when missing
+        #[arg(short, long)]
+        to: Pubkey,
+        /// Amount in tokens, e.g. 1.5
+        #[arg(short, long)]
+        amount: String,
+        /// Source token account the fee payer is a delegate of (defaults to the fee payer's ATA,
+        /// or the multisig's with --multisig)
+        #[arg(long)]
+        from: Option<Pubkey>,
+        #[command(flatten)]
+        multisig: MultisigArgs,
+    },
+    /// Send tokens from the fee payer's ATA to every `recipient,amount` row of a CSV file,
+    /// resuming an interrupted run from its state file
+    Distribute {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// CSV of recipient wallets and amounts in tokens, e.g. 1.5
+        csv: PathBuf,
+        /// File recording delivered rows [default: <csv>.state.json]
+        #[arg(long)]
+        state: Option<PathBuf>,
+        /// Send rows in fewer, larger v0 transactions loading their accounts from lookup
+        /// tables, at the cost of creating, filling and deactivating the tables
+        #[arg(long)]
+        lookup_tables: bool,
+    },
+    /// Let a delegate transfer or burn up to an amount from a token account of the fee payer
+    Approve {
+        #[arg(short, long)]
+        mint: Pubkey,
+        #[arg(short, long)]
+        delegate: Pubkey,
+        /// Allowance in tokens, e.g. 1.5
+        #[arg(short, long)]
+        amount: String,
+        /// Token account to delegate (defaults to the fee payer's ATA)
+        #[arg(long)]
+        account: Option<Pubkey>,
+    },
+    /// Remove the delegate of a token account of the fee payer
+    Revoke {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Token account to revoke (defaults to the fee payer's ATA)
+        #[arg(long)]
+        account: Option<Pubkey>,
+    },
+    /// Burn tokens from a token account, signed by its owner or delegate (the fee payer)
+    Burn {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// Amount in tokens, e.g. 1.5
+        #[arg(short, long)]
+        amount: String,
+        /// Token account to burn from (defaults to the fee payer's ATA)
+        #[arg(long)]
+        account: Option<Pubkey>,
+    },
+    /// Freeze token accounts of a mint, the fee payer must be the freeze authority
+    Freeze {
+        #[arg(short, long)]
+        mint: Pubkey,
+        #[command(flatten)]
+        target: FreezeTarget,
+        /// Freeze every holder of the mint
+        #[arg(
+            long,
+            conflicts_with_all = ["account", "owner"],
+            required_unless_present_any = ["account", "owner"]
+        )]
+        all: bool,
+    },
+    /// Thaw a frozen token account, the fee payer must be the freeze authority
+    Thaw {
+        #[arg(short, long)]
+        mint: Pubkey,
+        #[command(flatten)]
+        target: FreezeTarget,
+    },
+    /// Rotate or revoke an authority of a mint or token account held by the fee payer
+    SetAuthority {
+        /// Mint for mint/freeze, token account for owner, either for close
+        target: Pubkey,
+        /// mint, freeze, owner or close
+        #[arg(short = 't', long = "type", value_parser = parse_authority_type)]
+        authority_type: AuthorityType,
+        /// New authority; omit together with --disable to revoke it
+        #[arg(long, required_unless_present = "disable")]
+        new_authority: Option<Pubkey>,
+        /// Revoke the authority permanently, e.g. to fix a mint's supply
+        #[arg(long, conflicts_with = "new_authority")]
+        disable: bool,
+    },
+    /// Close an empty token account of the fee payer and reclaim its rent
+    CloseAccount {
+        /// Token account to close
+        #[arg(required_unless_present = "sweep")]
+        account: Option<Pubkey>,
+        /// Close every empty token account of the fee payer instead
+        #[arg(long, conflicts_with = "account")]
+        sweep: bool,
+        /// Where the rent goes (defaults to the fee payer)
+        #[arg(long)]
+        destination: Option<Pubkey>,
+    },
+    /// Wrap SOL of the fee payer into its wrapped SOL ATA
+    Wrap {
+        /// Amount in SOL, e.g. 0.5
+        amount: String,
+    },
+    /// Unwrap all wrapped SOL of the fee payer by closing its wrapped SOL ATA
+    Unwrap {
+        /// Where the SOL goes (defaults to the fee payer)
+        #[arg(long)]
+        destination: Option<Pubkey>,
+    },
+    /// Airdrop lamports to the fee payer
+    Airdrop {
+        #[arg(short, long, default_value_t = 1_000_000_000)]
+        lamports: u64,
+    },
+    /// Make sure an account holds a minimum balance, airdropping or using the funder
+    Fund {
+        /// Account to fund (defaults to the fee payer)
+        pubkey: Option<Pubkey>,
+        #[arg(long, default_value_t = 1_000_000_000)]
+        min_lamports: u64,
+        /// Lamports to request from the faucet (defaults to the shortfall)
+        #[arg(long)]
+        airdrop_lamports: Option<u64>,
+        /// Seconds to wait for the airdrop before falling back to the funder
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// Create an address lookup table owned by the fee payer, optionally holding addresses
+    CreateLookupTable { addresses: Vec<Pubkey> },
+    /// Add addresses to a lookup table of the fee payer
+    ExtendLookupTable {
+        table: Pubkey,
+        #[arg(required = true)]
+        addresses: Vec<Pubkey>,
+    },
+    /// Deactivate a lookup table of the fee payer, so it can be closed once it cools down
+    DeactivateLookupTable { table: Pubkey },
+    /// Close a deactivated lookup table of the fee payer, recovering its rent
+    CloseLookupTable {
+        table: Pubkey,
+        /// Where the rent goes (defaults to the fee payer)
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+    },
+    /// Create a durable nonce account funded by the fee payer
+    CreateNonceAccount {
+        /// Authority allowed to advance the nonce (defaults to the fee payer)
+        #[arg(long)]
+        authority: Option<Pubkey>,
+    },
+    /// Show the authority and stored nonce of a nonce account
+    NonceInfo { address: Pubkey },
+    /// Advance a nonce account of the fee payer, invalidating transactions signed with its nonce
+    AdvanceNonce { address: Pubkey },
+    /// Hand a nonce account of the fee payer over to another authority
+    AuthorizeNonce {
+        address: Pubkey,
+        new_authority: Pubkey,
+    },
+    /// Withdraw SOL from a nonce account of the fee payer, closing it when emptied
+    WithdrawFromNonce {
+        address: Pubkey,
+        /// Amount in SOL, e.g. 0.5
+        amount: String,
+        /// Where the SOL goes (defaults to the fee payer)
+        #[arg(long)]
+        destination: Option<Pubkey>,
+    },
+    /// Add signatures to a transaction printed by --sign-only, printing it again
+    Sign {
+        /// The transaction, base64 or JSON, `-` to read it from stdin
+        transaction: String,
+        /// Keypair signing it (same spec as --keypair), repeated for each signer
+        /// [default: the fee payer]
+        #[arg(long = "signer")]
+        signers: Vec<KeypairSource>,
+    },
+    /// Send a fully signed transaction printed by --sign-only or `sign`
+    Broadcast {
+        /// The transaction, base64 or JSON, `-` to read it from stdin
+        transaction: String,
+    },
+    /// Set a metadata field of a token-2022 mint, the fee payer must be the update authority
+    UpdateMetadata {
+        #[arg(short, long)]
+        mint: Pubkey,
+        /// name, symbol, uri or any additional key
+        #[arg(short, long, value_parser = parse_field)]
+        field: Field,
+        value: String,
+    },
+    /// Print the state of a mint account
+    MintInfo { address: Pubkey },
+    /// Print the state of a token account
+    AccountInfo { address: Pubkey },
+}
+
+/// The token account a freeze or thaw applies to.
+#[derive(Args)]
+#[group(multiple = false)]
+struct FreezeTarget {
+    /// Token account address
+    #[arg(long)]
+    account: Option<Pubkey>,
+    /// Owner whose ATA to use
+    #[arg(long)]
+    owner: Option<Pubkey>,
+}
+
+impl FreezeTarget {
+    fn address(&self, program: TokenProgram, mint: &Pubkey) -> Option<Pubkey> {
+        self.account
+            .or_else(|| Some(program.ata_address(&self.owner?, mint)))
+    }
+}
+
+/// A multisig authority and the keypairs approving on its behalf.
+#[derive(Args)]
+struct MultisigArgs {
+    /// Multisig acting as the authority instead of the fee payer
+    #[arg(long)]
+    multisig: Option<Pubkey>,
+    /// Keypair of a multisig signer (same spec as --keypair), repeated for each signer
+    #[arg(long = "signer", requires = "multisig")]
+    signers: Vec<KeypairSource>,
+}
+
+impl MultisigArgs {
+    /// The authority to sign for, and the keypairs signing for it.
+    fn authority(
+        &self,
+        ctx: &Ctx,
+        payer: &Arc<dyn Signer + Send + Sync>,
+    ) -> Result<(Pubkey, Vec<Arc<dyn Signer + Send + Sync>>)> {
+        match self.multisig {
+            Some(multisig) => Ok((
+                multisig,
+                self.signers
+                    .iter()
+                    .map(|source| ctx.signer(source))
+                    .collect::<Result<_>>()?,
+            )),
+            None => Ok((payer.pubkey(), vec![payer.clone()])),
+        }
+    }
+}
+
+/// Token-2022 extensions for `create-mint`; any of them implies `--program token-2022`.
+/// Authorities of the configurable extensions default to the fee payer.
+#[derive(Args)]
+struct ExtensionArgs {
+    /// Transfer fee in basis points of the transferred amount
+    #[arg(long)]
+    transfer_fee_bps: Option<u16>,
+    /// Cap on the transfer fee, in tokens [default: no cap]
+    #[arg(long, requires = "transfer_fee_bps")]
+    max_fee: Option<String>,
+    /// Interest rate in basis points per year, applied to displayed amounts
+    #[arg(long, allow_negative_numbers = true)]
+    interest_rate: Option<i16>,
+    /// Forbid transfers between holders
+    #[arg(long)]
+    non_transferable: bool,
+    /// Delegate allowed to transfer or burn from any account of the mint
+    #[arg(long)]
+    permanent_delegate: Option<Pubkey>,
+    /// Allow closing the mint once its supply is zero [default authority: the fee payer]
+    #[arg(long, num_args = 0..=1)]
+    close_authority: Option<Option<Pubkey>>,
+    /// State of new token accounts: initialized or frozen
+    #[arg(long, value_parser = parse_account_state)]
+    default_account_state: Option<AccountState>,
+    /// Account holding the metadata [default: the mint itself]
+    #[arg(long, num_args = 0..=1)]
+    metadata_pointer: Option<Option<Pubkey>>,
+    /// Token name, stored with the symbol and uri in the mint's metadata
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long, requires = "name")]
+    symbol: Option<String>,
+    /// Uri of the off-chain json metadata
+    #[arg(long, requires = "name")]
+    uri: Option<String>,
+}
+
+impl ExtensionArgs {
+    fn extensions(
+        &self,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<MintExtensions> {
+        let maximum_fee = match &self.max_fee {
+            Some(max_fee) => ui_amount_to_amount(max_fee, decimals)?,
+            None => u64::MAX,
+        };
+        Ok(MintExtensions {
+            transfer_fee: self.transfer_fee_bps.map(|basis_points| TransferFee {
+                basis_points,
+                maximum_fee,
+                config_authority: Some(*authority),
+                withdraw_authority: Some(*authority),
+            }),
+            interest_rate: self.interest_rate.map(|rate| InterestRate {
+                rate,
+                authority: Some(*authority),
+            }),
+            non_transferable: self.non_transferable,
+            permanent_delegate: self.permanent_delegate,
+            close_authority: self
+                .close_authority
+                .map(|close| close.unwrap_or(*authority)),
+            default_account_state: self.default_account_state,
+            metadata_pointer: match (self.metadata_pointer, &self.name) {
+                (Some(address), _) => Some(address.unwrap_or(*mint)),
+                (None, Some(_)) => Some(*mint),
+                (None, None) => None,
+            }
+            .map(|address| MetadataPointer {
+                authority: Some(*authority),
+                metadata_address: Some(address),
+            }),
+            metadata: self.name.as_ref().map(|name| Metadata {
+                name: name.clone(),
+                symbol: self.symbol.clone().unwrap_or_default(),
+                uri: self.uri.clone().unwrap_or_default(),
+                additional_metadata: Vec::new(),
+            }),
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let cluster = ClusterConfig::resolve(ClusterSelection {
+        cluster: cli.cluster,
+        ws_url: cli.ws_url,
+        commitment: cli.commitment,
+        profile: cli.profile,
+        config_path: cli.config,
+    })?;
+    let unit_margin = if cli.no_compute_unit_limit {
+        None
+    } else {
+        cli.compute_unit_margin
+            .or(ComputeBudget::DEFAULT.unit_margin)
+    };
+    let compute_budget = ComputeBudget {
+        unit_margin,
+        priority_fee: cli
+            .priority_fee
+            .unwrap_or(ComputeBudget::DEFAULT.priority_fee),
+        max_unit_price: cli.max_priority_fee,
+    };
+    let mut ctx = Ctx {
+        client: cluster.client(),
+        keypair: cli.keypair,
+        funder: cli.funder,
+        program: cli.program,
+        export_format: cli.export_format,
+        send: SendOptions {
+            compute_budget,
+            nonce: None,
+            mode: if cli.simulate {
+                SendMode::Simulate
+            } else if cli.sign_only {
+                SendMode::SignOnly
+            } else {
+                SendMode::Send
+            },
+        },
+    };
+    if let Some(account) = cli.nonce {
+        let authority = match &cli.nonce_authority {
+            Some(source) => ctx.signer(source)?,
+            None => ctx.fee_payer()?,
+        };
+        ctx.send.nonce = Some(DurableNonce { account, authority });
+    }
+    run(&ctx, cli.command).await
+}
+
+/// Everything the subcommands share, resolved from the global flags.
+struct Ctx {
+    client: RpcClient,
+    keypair: Option<KeypairSource>,
+    funder: Option<KeypairSource>,
+    program: Option<TokenProgram>,
+    export_format: ExportFormat,
+    /// Options of every transaction a subcommand sends.
+    send: SendOptions,
+}
+
+impl Ctx {
+    /// The `--program` choice, or the program owning `mint`.
+    async fn program_of(&self, mint: &Pubkey) -> Result<TokenProgram> {
+        match self.program {
+            Some(program) => Ok(program),
+            None => TokenProgram::of_mint(&self.client, mint).await,
+        }
+    }
+
+    fn fee_payer(&self) -> Result<Arc<dyn Signer + Send + Sync>> {
+        match &self.keypair {
+            Some(source) => self.signer(source),
+            None => Ok(Arc::new(load_default_keypair()?)),
+        }
+    }
+
+    /// The keypair of `source`, which in sign-only mode may also be a bare pubkey.
+    fn signer(&self, source: &KeypairSource) -> Result<Arc<dyn Signer + Send + Sync>> {
+        source
+            .signer(self.send.mode == SendMode::SignOnly)
+            .with_context(|| format!("failed to load keypair `{source}`"))
+    }
+
+    /// What an operation produced once its transaction was sent, or `None` when it was only
+    /// simulated or exported and printed instead.
+    async fn sent<T>(
+        &self,
+        operation: impl Future<Output = Result<SendOutcome<T>>>,
+    ) -> Result<Option<T>> {
+        match operation.await? {
+            SendOutcome::Sent(sent) => Ok(Some(sent)),
+            SendOutcome::Simulated(simulation) => {
+                print_simulation(&self.client, &simulation).await;
+                if !simulation.succeeded() {
+                    bail!("{simulation}");
+                }
+                Ok(None)
+            }
+            SendOutcome::Exported(exported) => {
+                print_exported(&exported, self.export_format)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn funding_options(&self) -> Result<FundingOptions> {
+        Ok(FundingOptions {
+            funder: self.funder.as_ref().map(load).transpose()?,
+            compute_budget: self.send.compute_budget,
+            ..FundingOptions::default()
+        })
+    }
+}
+
+fn load(source: &KeypairSource) -> Result<Keypair> {
+    source
+        .load()
+        .with_context(|| format!("failed to load keypair `{source}`"))
+}
+
+/// An exported transaction, or with `-` one read from stdin.
+fn read_exported(transaction: &str) -> Result<ExportedTransaction> {
+    if transaction != "-" {
+        return ExportedTransaction::parse(transaction);
+    }
+    let mut stdin = String::new();
+    io::stdin().read_to_string(&mut stdin)?;
+    ExportedTransaction::parse(&stdin)
+}
+
+/// The transaction alone on stdout, so it can be piped to the next signer.
+fn print_exported(exported: &ExportedTransaction, format: ExportFormat) -> Result<()> {
+    println!("{}", exported.export(format)?);
+    eprintln!("{exported}");
+    Ok(())
+}
+
+fn display_authority(authority: Option<Pubkey>) -> String {
+    authority.map_or("none".to_string(), |authority| authority.to_string())
+}
+
+fn print_nonce_account(address: &Pubkey, nonce: &NonceAccount) {
+    println!("Nonce Account: {}", address);
+    println!("Authority: {}", nonce.authority);
+    println!("Nonce: {}", nonce.nonce);
+    println!(
+        "Balance: {} SOL",
+        amount_to_ui_amount(nonce.lamports, NATIVE_DECIMALS)
+    );
+}
+
+fn print_state_change(change: &StateChange) {
+    println!(
+        "{}: {:?} -> {:?}",
+        change.address, change.before, change.account.state
+    );
+}
+
+fn print_state_changes(changes: &[StateChange]) {
+    for change in changes {
+        println!("Transaction Signature: {}", change.signature);
+        print_state_change(change);
+    }
+}
+
+fn print_mint(mint: &Mint) {
+    println!(
+        "Supply: {}",
+        amount_to_ui_amount(mint.supply, mint.decimals)
+    );
+    println!("Decimals: {}", mint.decimals);
+    println!(
+        "Mint authority: {}",
+        display_authority(mint.mint_authority.into())
+    );
+    println!(
+        "Freeze authority: {}",
+        display_authority(mint.freeze_authority.into())
+    );
+}
+
+fn print_token_account(account: &Account, decimals: u8) {
+    println!("Mint: {}", account.mint);
+    println!("Owner: {}", account.owner);
+    println!("Balance: {}", amount_to_ui_amount(account.amount, decimals));
+    println!("State: {:?}", account.state);
+    print_delegation(account, decimals);
+    if let COption::Some(close_authority) = account.close_authority {
+        println!("Close authority: {}", close_authority);
+    }
+    if let COption::Some(reserve) = account.is_native {
+        println!("Rent-exempt reserve: {} lamports", reserve);
+    }
+}
+
+fn print_delegation(account: &Account, decimals: u8) {
+    match account.delegate {
+        COption::Some(delegate) => {
+            println!("Delegate: {}", delegate);
+            println!(
+                "Delegated amount: {}",
+                amount_to_ui_amount(account.delegated_amount, decimals)
+            );
+        }
+        COption::None => println!("Delegate: none"),
+    }
+}
+
+async fn print_simulation(client: &RpcClient, simulation: &Simulation) {
+    match &simulation.err {
+        Some(err) => println!("Simulation failed: {}", err),
+        None => println!("Simulation succeeded, nothing was sent"),
+    }
+    if let Some(units) = simulation.units_consumed {
+        println!("Compute units consumed: {}", units);
+    }
+    println!("Logs:");
+    for log in &simulation.logs {
+        println!("  {}", log);
+    }
+    for (address, state) in &simulation.accounts {
+        match state {
+            PostState::TokenAccount(account) => {
+                //A mint created by the same transaction only exists in the simulation
+                let decimals = match simulation.mint(&account.mint) {
+                    Some(mint) => Some(mint.decimals),
+                    None => get_mint(client, &account.mint)
+                        .await
+                        .ok()
+                        .map(|mint| mint.decimals),
+                };
+                let balance = match decimals {
+                    Some(decimals) => amount_to_ui_amount(account.amount, decimals),
+                    None => format!("{} (base units)", account.amount),
+                };
+                println!(
+                    "Token account {}: mint {}, owner {}, balance {}, {:?}",
+                    address, account.mint, account.owner, balance, account.state
+                );
+            }
+            PostState::Mint(mint) => println!(
+                "Mint {}: supply {}, decimals {}",
+                address,
+                amount_to_ui_amount(mint.supply, mint.decimals),
+                mint.decimals
+            ),
+            PostState::Closed => println!("Account {}: closed", address),
+        }
+    }
+}
+
+async fn run(ctx: &Ctx, command: Command) -> Result<()> {
+    let client = &ctx.client;
+    match command {
+        Command::CreateMint {
+            decimals,
+            extensions,
+        } => {
+            let payer = ctx.fee_payer()?;
+            let mint = Keypair::new();
+            let extensions = extensions.extensions(&payer.pubkey(), &mint.pubkey(), decimals)?;
+            let program = match ctx.program {
+                Some(program) => program,
+                None if !extensions.is_empty() => TokenProgram::Token2022,
+                None => TokenProgram::default(),
+            };
+            let Some(mint) = ctx
+                .sent(create_mint_with_extensions(
+                    client,
+                    &payer,
+                    &mint,
+                    program,
+                    decimals,
+                    &extensions,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Mint Address: {}", mint.address);
+            println!("Transaction Signature: {}", mint.signature);
+            print_mint(&mint.state);
+            if !mint.extensions.is_empty() {
+                println!("Extensions: {:?}", mint.extensions);
+            }
+        }
+        Command::CreateAccount { mint, owner } => {
+            let payer = ctx.fee_payer()?;
+            let owner = owner.unwrap_or(payer.pubkey());
+            let program = ctx.program_of(&mint).await?;
+            let Some(account) = ctx
+                .sent(create_token_account(
+                    client, &payer, program, &mint, &owner, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Token account created at : {}", account.address);
+            println!("Transaction Signature: {}", account.signature);
+            let decimals = get_mint(client, &mint).await?.decimals;
+            print_token_account(&account.state, decimals);
+        }
+        Command::CreateAta { mint, owner } => {
+            let payer = ctx.fee_payer()?;
+            let owner = owner.unwrap_or(payer.pubkey());
+            let program = ctx.program_of(&mint).await?;
+            let Some(ata) = ctx
+                .sent(get_or_create_ata(
+                    client, &payer, program, &mint, &owner, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            match ata.signature {
+                Some(signature) => {
+                    println!("Associated-Token-account created at : {}", ata.address);
+                    println!("Transaction Signature: {}", signature);
+                }
+                None => println!(
+                    "Associated-Token-account already exists at : {}",
+                    ata.address
+                ),
+            }
+            let decimals = get_mint(client, &mint).await?.decimals;
+            print_token_account(&ata.state, decimals);
+        }
+        Command::CreateMultisig { m, signers } => {
+            let payer = ctx.fee_payer()?;
+            let program = ctx.program.unwrap_or_default();
+            let Some(multisig) = ctx
+                .sent(create_multisig(
+                    client, &payer, program, m, &signers, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Multisig created at : {}", multisig.address);
+            println!("Transaction Signature: {}", multisig.signature);
+            println!("{:#?}", multisig.state);
+        }
+        Command::MintTo {
+            mint,
+            amount,
+            to,
+            multisig,
+        } => {
+            let payer = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let amount = ui_amount_to_amount(&amount, decimals)?;
+            let (authority, signers) = multisig.authority(ctx, &payer)?;
+            let destination = to.unwrap_or_else(|| program.ata_address(&payer.pubkey(), &mint));
+            let Some(minted) = ctx
+                .sent(mint_to_with_signers(
+                    client,
+                    &payer,
+                    program,
+                    &mint,
+                    &destination,
+                    &authority,
+                    &signers
+                        .iter()
+                        .map(|signer| signer as &dyn Signer)
+                        .collect::<Vec<_>>(),
+                    amount,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!(
+                "Minted {} to {}",
+                amount_to_ui_amount(amount, decimals),
+                destination
+            );
+            println!("Transaction Signature: {}", minted.signature);
+            println!(
+                "Mint supply: {}",
+                amount_to_ui_amount(minted.mint.supply, decimals)
+            );
+            println!(
+                "Account balance: {}",
+                amount_to_ui_amount(minted.account.amount, decimals)
+            );
+        }
+        Command::Transfer {
+            mint,
+            to,
+            amount,
+            from,
+            multisig,
+        } => {
+            let payer = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let amount = ui_amount_to_amount(&amount, decimals)?;
+            let (authority, signers) = multisig.authority(ctx, &payer)?;
+            let source = from.unwrap_or_else(|| program.ata_address(&authority, &mint));
+            let destination = program.ata_address(&to, &mint);
+            let Some(transfer) = ctx
+                .sent(transfer_checked_to_owner(
+                    client,
+                    &payer,
+                    program,
+                    &source,
+                    &mint,
+                    &to,
+                    &authority,
+                    &signers
+                        .iter()
+                        .map(|signer| signer as &dyn Signer)
+                        .collect::<Vec<_>>(),
+                    amount,
+                    decimals,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!(
+                "Transferred {} from {} to {}",
+                amount_to_ui_amount(amount, decimals),
+                source,
+                destination
+            );
+            println!("Transaction Signature: {}", transfer.signature);
+            println!(
+                "Source balance: {}",
+                amount_to_ui_amount(transfer.source.amount, decimals)
+            );
+            println!(
+                "Destination balance: {}",
+                amount_to_ui_amount(transfer.destination.amount, decimals)
+            );
+            if from.is_some() {
+                print_delegation(&transfer.source, decimals);
+            }
+        }
+        Command::Distribute {
+            mint,
+            csv,
+            state,
+            lookup_tables,
+        } => {
+            let payer = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let contents = std::fs::read_to_string(&csv)
+                .with_context(|| format!("failed to read {}", csv.display()))?;
+            let recipients = parse_recipients(&contents, decimals)?;
+            let state = state.unwrap_or_else(|| {
+                let mut path = csv.into_os_string();
+                path.push(".state.json");
+                path.into()
+            });
+            let Some(distribution) = ctx
+                .sent(distribute(
+                    client,
+                    &payer,
+                    program,
+                    &mint,
+                    &recipients,
+                    &state,
+                    lookup_tables,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            for signature in &distribution.signatures {
+                println!("Transaction Signature: {}", signature);
+            }
+            println!(
+                "Sent {} rows in {} transactions, {} already sent earlier, {} ATAs created",
+                distribution.sent,
+                distribution.signatures.len(),
+                distribution.resumed,
+                distribution.created_accounts
+            );
+            for table in &distribution.lookup_tables {
+                println!(
+                    "Deactivated lookup table {}, close it once it cools down",
+                    table
+                );
+            }
+            println!("State file: {}", state.display());
+        }
+        Command::Approve {
+            mint,
+            delegate,
+            amount,
+            account,
+        } => {
+            let owner = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let amount = ui_amount_to_amount(&amount, decimals)?;
+            let account = account.unwrap_or_else(|| program.ata_address(&owner.pubkey(), &mint));
+            let Some(approved) = ctx
+                .sent(approve_checked(
+                    client, &owner, program, &account, &mint, &delegate, amount, decimals,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", approved.signature);
+            print_delegation(&approved.account, decimals);
+        }
+        Command::Revoke { mint, account } => {
+            let owner = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let account = account.unwrap_or_else(|| program.ata_address(&owner.pubkey(), &mint));
+            let Some(revoked) = ctx
+                .sent(revoke(client, &owner, program, &account, &ctx.send))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", revoked.signature);
+            print_delegation(&revoked.account, decimals);
+        }
+        Command::Burn {
+            mint,
+            amount,
+            account,
+        } => {
+            let authority = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let decimals = get_mint(client, &mint).await?.decimals;
+            let amount = ui_amount_to_amount(&amount, decimals)?;
+            let account =
+                account.unwrap_or_else(|| program.ata_address(&authority.pubkey(), &mint));
+            let Some(burned) = ctx
+                .sent(burn_checked(
+                    client, &authority, program, &account, &mint, amount, decimals, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!(
+                "Burned {} from {}",
+                amount_to_ui_amount(amount, decimals),
+                account
+            );
+            println!("Transaction Signature: {}", burned.signature);
+            println!(
+                "Mint supply: {}",
+                amount_to_ui_amount(burned.mint.supply, decimals)
+            );
+            println!(
+                "Account balance: {}",
+                amount_to_ui_amount(burned.account.amount, decimals)
+            );
+        }
+        Command::Freeze { mint, target, .. } => {
+            let authority = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            match target.address(program, &mint) {
+                Some(account) => {
+                    let Some(change) = ctx
+                        .sent(freeze_account(
+                            client, &authority, program, &account, &mint, &ctx.send,
+                        ))
+                        .await?
+                    else {
+                        return Ok(());
+                    };
+                    println!("Transaction Signature: {}", change.signature);
+                    print_state_change(&change);
+                }
+                None => {
+                    let changes = match ctx
+                        .sent(freeze_all(client, &authority, program, &mint, &ctx.send))
+                        .await
+                    {
+                        Ok(Some(changes)) => changes,
+                        Ok(None) => return Ok(()),
+                        Err(err) => {
+                            //Whoever is left unfrozen is frozen by running again
+                            if let Some(partial) = err.downcast_ref::<FreezeAllError>() {
+                                print_state_changes(&partial.frozen);
+                            }
+                            return Err(err);
+                        }
+                    };
+                    print_state_changes(&changes);
+                    println!("Froze {} accounts", changes.len());
+                }
+            }
+        }
+        Command::Thaw { mint, target } => {
+            let authority = ctx.fee_payer()?;
+            let program = ctx.program_of(&mint).await?;
+            let account = target
+                .address(program, &mint)
+                .context("either --account or --owner is required")?;
+            let Some(change) = ctx
+                .sent(thaw_account(
+                    client, &authority, program, &account, &mint, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", change.signature);
+            print_state_change(&change);
+        }
+        Command::SetAuthority {
+            target,
+            authority_type,
+            new_authority,
+            ..
+        } => {
+            let authority = ctx.fee_payer()?;
+            let program = ctx.program_of(&target).await?;
+            let Some(change) = ctx
+                .sent(set_authority(
+                    client,
+                    &authority,
+                    program,
+                    &target,
+                    authority_type.clone(),
+                    new_authority.as_ref(),
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", change.signature);
+            println!(
+                "{:?} authority: {} -> {}",
+                authority_type,
+                display_authority(change.previous),
+                display_authority(change.current)
+            );
+        }
+        Command::CloseAccount {
+            account,
+            destination,
+            ..
+        } => {
+            let owner = ctx.fee_payer()?;
+            let destination = destination.unwrap_or(owner.pubkey());
+            match account {
+                Some(account) => {
+                    let program = ctx.program_of(&account).await?;
+                    let Some(closed) = ctx
+                        .sent(close_account(
+                            client,
+                            &owner,
+                            program,
+                            &account,
+                            &destination,
+                            &ctx.send,
+                        ))
+                        .await?
+                    else {
+                        return Ok(());
+                    };
+                    println!("Transaction Signature: {}", closed.signature);
+                    println!(
+                        "Closed {}, recovered {} lamports",
+                        closed.address, closed.lamports
+                    );
+                }
+                None => {
+                    let Some(sweep) = ctx
+                        .sent(sweep_empty_accounts(
+                            client,
+                            &owner,
+                            &destination,
+                            &ctx.send,
+                        ))
+                        .await?
+                    else {
+                        return Ok(());
+                    };
+                    for closed in &sweep.closed {
+                        println!(
+                            "Closed {} ({} lamports) in {}",
+                            closed.address, closed.lamports, closed.signature
+                        );
+                    }
+                    for skipped in &sweep.skipped {
+                        println!("Skipped {}", skipped);
+                    }
+                    println!(
+                        "Closed {} accounts, recovered {} lamports",
+                        sweep.closed.len(),
+                        sweep.lamports()
+                    );
+                }
+            }
+        }
+        Command::Wrap { amount } => {
+            let owner = ctx.fee_payer()?;
+            let program = ctx.program.unwrap_or_default();
+            let lamports = ui_amount_to_amount(&amount, NATIVE_DECIMALS)?;
+            let Some(wrapped) = ctx
+                .sent(wrap_sol(client, &owner, program, lamports, &ctx.send))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!(
+                "Wrapped {} SOL into {}",
+                amount_to_ui_amount(lamports, NATIVE_DECIMALS),
+                wrapped.address
+            );
+            println!("Transaction Signature: {}", wrapped.signature);
+            println!(
+                "Wrapped balance: {}",
+                amount_to_ui_amount(wrapped.account.amount, NATIVE_DECIMALS)
+            );
+        }
+        Command::Unwrap { destination } => {
+            let owner = ctx.fee_payer()?;
+            let program = ctx.program.unwrap_or_default();
+            let destination = destination.unwrap_or(owner.pubkey());
+            let Some(closed) = ctx
+                .sent(unwrap_sol(client, &owner, program, &destination, &ctx.send))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", closed.signature);
+            println!(
+                "Unwrapped {} SOL to {}",
+                amount_to_ui_amount(closed.lamports, NATIVE_DECIMALS),
+                destination
+            );
+        }
+        Command::Airdrop { lamports } => {
+            if ctx.send.mode != SendMode::Send {
+                bail!("airdrops are not transactions and cannot be simulated or exported");
+            }
+            let payer = ctx.fee_payer()?;
+            let signature = airdrop(client, &payer.pubkey(), lamports).await?;
+            println!("Airdrop Signature: {}", signature);
+            println!("Balance: {}", client.get_balance(&payer.pubkey()).await?);
+        }
+        Command::Fund {
+            pubkey,
+            min_lamports,
+            airdrop_lamports,
+            timeout,
+        } => {
+            if ctx.send.mode != SendMode::Send {
+                bail!("funding may request an airdrop, which cannot be simulated or exported");
+            }
+            let pubkey = match pubkey {
+                Some(pubkey) => pubkey,
+                None => ctx.fee_payer()?.pubkey(),
+            };
+            let options = FundingOptions {
+                airdrop_lamports,
+                timeout: Duration::from_secs(timeout),
+                ..ctx.funding_options()?
+            };
+            let funded = ensure_funded(client, &pubkey, min_lamports, &options).await?;
+            println!("{funded}");
+        }
+        Command::CreateLookupTable { addresses } => {
+            let authority = ctx.fee_payer()?;
+            let Some(created) = ctx
+                .sent(create_lookup_table(
+                    client, &authority, &addresses, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Lookup Table Address: {}", created.table.address);
+            for signature in &created.signatures {
+                println!("Transaction Signature: {}", signature);
+            }
+        }
+        Command::ExtendLookupTable { table, addresses } => {
+            let authority = ctx.fee_payer()?;
+            let Some(signatures) = ctx
+                .sent(extend_lookup_table(
+                    client, &authority, &table, &addresses, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            for signature in &signatures {
+                println!("Transaction Signature: {}", signature);
+            }
+            let table = get_lookup_table(client, &table).await?;
+            println!("Addresses: {}", table.addresses.len());
+        }
+        Command::DeactivateLookupTable { table } => {
+            let authority = ctx.fee_payer()?;
+            let Some(signature) = ctx
+                .sent(deactivate_lookup_table(
+                    client, &authority, &table, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", signature);
+        }
+        Command::CloseLookupTable { table, recipient } => {
+            let authority = ctx.fee_payer()?;
+            let recipient = recipient.unwrap_or(authority.pubkey());
+            let Some(signature) = ctx
+                .sent(close_lookup_table(
+                    client, &authority, &table, &recipient, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", signature);
+        }
+        Command::CreateNonceAccount { authority } => {
+            let payer = ctx.fee_payer()?;
+            let authority = authority.unwrap_or(payer.pubkey());
+            let Some(created) = ctx
+                .sent(create_nonce_account(client, &payer, &authority, &ctx.send))
+                .await?
+            else {
+                return Ok(());
+            };
+            print_nonce_account(&created.address, &created.state);
+            println!("Transaction Signature: {}", created.signature);
+        }
+        Command::NonceInfo { address } => {
+            let nonce = get_nonce_account(client, &address).await?;
+            print_nonce_account(&address, &nonce);
+            println!(
+                "Fee per signature: {} lamports",
+                nonce.lamports_per_signature
+            );
+        }
+        Command::AdvanceNonce { address } => {
+            let authority = ctx.fee_payer()?;
+            let Some(nonce) = ctx
+                .sent(advance_nonce(client, &authority, &address, &ctx.send))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Nonce: {}", nonce.nonce);
+        }
+        Command::AuthorizeNonce {
+            address,
+            new_authority,
+        } => {
+            let authority = ctx.fee_payer()?;
+            let Some(signature) = ctx
+                .sent(authorize_nonce_account(
+                    client,
+                    &authority,
+                    &address,
+                    &new_authority,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", signature);
+        }
+        Command::WithdrawFromNonce {
+            address,
+            amount,
+            destination,
+        } => {
+            let authority = ctx.fee_payer()?;
+            let destination = destination.unwrap_or(authority.pubkey());
+            let lamports = ui_amount_to_amount(&amount, NATIVE_DECIMALS)?;
+            let Some(signature) = ctx
+                .sent(withdraw_from_nonce_account(
+                    client,
+                    &authority,
+                    &address,
+                    &destination,
+                    lamports,
+                    &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", signature);
+        }
+        Command::Sign {
+            transaction,
+            signers,
+        } => {
+            let mut exported = read_exported(&transaction)?;
+            let signers = if signers.is_empty() {
+                vec![ctx.fee_payer()?]
+            } else {
+                signers
+                    .iter()
+                    .map(|source| ctx.signer(source))
+                    .collect::<Result<_>>()?
+            };
+            exported.sign(
+                &signers
+                    .iter()
+                    .map(|signer| signer as &dyn Signer)
+                    .collect::<Vec<_>>(),
+            )?;
+            print_exported(&exported, ctx.export_format)?;
+        }
+        Command::Broadcast { transaction } => {
+            let exported = read_exported(&transaction)?;
+            let signature = broadcast(client, &exported).await?;
+            println!("Transaction Signature: {}", signature);
+        }
+        Command::UpdateMetadata { mint, field, value } => {
+            let authority = ctx.fee_payer()?;
+            let Some(updated) = ctx
+                .sent(update_metadata(
+                    client, &authority, &mint, field, value, &ctx.send,
+                ))
+                .await?
+            else {
+                return Ok(());
+            };
+            println!("Transaction Signature: {}", updated.signature);
+            println!("{:#?}", updated.metadata);
+        }
+        Command::MintInfo { address } => {
+            let mint = get_mint(client, &address).await?;
+            print_mint(&mint);
+            let extensions = get_mint_extensions(client, &address).await?;
+            if !extensions.is_empty() {
+                println!("Extensions: {:?}", extensions);
+            }
+            if let Some(metadata) = get_metadata(client, &address).await? {
+                println!("{:#?}", metadata);
+            }
+        }
+        Command::AccountInfo { address } => {
+            let account = get_token_account(client, &address).await?;
+            let decimals = get_mint(client, &account.mint).await?.decimals;
+            print_token_account(&account, decimals);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_flag_wins_over_the_environment() {
+        //The only test of the binary reading the variable
+        unsafe { std::env::set_var(CLUSTER_ENV, "mainnet") };
+        let mint = Pubkey::new_unique().to_string();
+        let from_env = Cli::try_parse_from(["spl-token", "mint-info", &mint]);
+        let from_flag = Cli::try_parse_from(["spl-token", "-u", "testnet", "mint-info", &mint]);
+        unsafe { std::env::remove_var(CLUSTER_ENV) };
+
+        assert_eq!(from_env.unwrap().cluster, Some(Cluster::Mainnet));
+        assert_eq!(from_flag.unwrap().cluster, Some(Cluster::Testnet));
+    }
+}
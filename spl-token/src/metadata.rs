@@ -0,0 +1,173 @@
+use anyhow::{Context, Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use spl_token_2022_interface::{
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+use spl_token_metadata_interface::{
+    instruction::{initialize, update_field},
+    state::{Field, TokenMetadata},
+};
+
+use crate::{
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Name, symbol and uri stored in a Token-2022 mint's metadata extension.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub additional_metadata: Vec<(String, String)>,
+}
+
+/// Result of an `update_metadata`, with the metadata read back afterwards.
+#[derive(Debug)]
+pub struct UpdatedMetadata {
+    pub signature: Signature,
+    pub metadata: TokenMetadata,
+}
+
+impl Metadata {
+    /// The on-chain representation for `mint`.
+    pub fn token_metadata(&self, mint: &Pubkey, update_authority: &Pubkey) -> TokenMetadata {
+        TokenMetadata {
+            update_authority: Some(*update_authority).try_into().unwrap_or_default(),
+            mint: *mint,
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            uri: self.uri.clone(),
+            additional_metadata: self.additional_metadata.clone(),
+        }
+    }
+
+    /// Bytes the metadata adds to the mint account once written.
+    pub fn extension_len(&self, mint: &Pubkey, update_authority: &Pubkey) -> Result<usize> {
+        Ok(self.token_metadata(mint, update_authority).tlv_size_of()?)
+    }
+
+    /// Instructions writing the metadata into an initialized mint, signed by the mint authority.
+    ///
+    /// The program reallocates the mint, so it must already hold the rent for the final size.
+    pub fn instructions(
+        &self,
+        program: TokenProgram,
+        mint: &Pubkey,
+        mint_authority: &Pubkey,
+    ) -> Vec<Instruction> {
+        let program_id = program.id();
+        let mut instructions = vec![initialize(
+            &program_id,
+            mint,
+            mint_authority,
+            mint,
+            mint_authority,
+            self.name.clone(),
+            self.symbol.clone(),
+            self.uri.clone(),
+        )];
+        for (key, value) in &self.additional_metadata {
+            instructions.push(update_field(
+                &program_id,
+                mint,
+                mint_authority,
+                Field::Key(key.clone()),
+                value.clone(),
+            ));
+        }
+        instructions
+    }
+}
+
+/// Fetch the metadata stored on a mint, `None` when it has none.
+pub async fn get_metadata(client: &RpcClient, mint: &Pubkey) -> Result<Option<TokenMetadata>> {
+    let account = client.get_account(mint).await?;
+    if TokenProgram::from_owner(&account.owner)? != TokenProgram::Token2022 {
+        return Ok(None);
+    }
+    let state = StateWithExtensions::<Mint>::unpack(&account.data)?;
+    Ok(state.get_variable_len_extension::<TokenMetadata>().ok())
+}
+
+/// Set one metadata field of a Token-2022 mint, topping up its rent when the value grows.
+pub async fn update_metadata(
+    client: &RpcClient,
+    update_authority: &dyn Signer,
+    mint: &Pubkey,
+    field: Field,
+    value: String,
+    options: &SendOptions,
+) -> Result<SendOutcome<UpdatedMetadata>> {
+    let account = client.get_account(mint).await?;
+    let program = TokenProgram::from_owner(&account.owner)?;
+    let current = StateWithExtensions::<Mint>::unpack(&account.data)?
+        .get_variable_len_extension::<TokenMetadata>()
+        .with_context(|| format!("mint {mint} has no token metadata"))?;
+    if Option::<Pubkey>::from(current.update_authority) != Some(update_authority.pubkey()) {
+        bail!(
+            "{} is not the metadata update authority of {mint}",
+            update_authority.pubkey()
+        );
+    }
+
+    let mut updated = current.clone();
+    updated.update(field.clone(), value.clone());
+    let new_len = account.data.len() - current.tlv_size_of()? + updated.tlv_size_of()?;
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(new_len)
+        .await?;
+
+    //The program reallocates the mint, it only needs to be funded for the new size
+    let mut instructions = Vec::new();
+    if rent > account.lamports {
+        instructions.push(transfer(
+            &update_authority.pubkey(),
+            mint,
+            rent - account.lamports,
+        ));
+    }
+    instructions.push(update_field(
+        &program.id(),
+        mint,
+        &update_authority.pubkey(),
+        field,
+        value,
+    ));
+
+    send_instructions(
+        client,
+        &instructions,
+        &update_authority.pubkey(),
+        &[update_authority],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(UpdatedMetadata {
+            signature,
+            metadata: get_metadata(client, mint)
+                .await?
+                .context("metadata missing after update")?,
+        })
+    })
+    .await
+}
+
+/// Parse `name`, `symbol`, `uri` or any other additional metadata key.
+pub fn parse_field(s: &str) -> Result<Field> {
+    Ok(match s {
+        "name" => Field::Name,
+        "symbol" => Field::Symbol,
+        "uri" => Field::Uri,
+        "" => bail!("metadata field must not be empty"),
+        key => Field::Key(key.to_string()),
+    })
+}
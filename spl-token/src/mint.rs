@@ -0,0 +1,193 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::create_account;
+use spl_token_2022_interface::{
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    instruction::{initialize_mint, mint_to as mint_to_instruction},
+    state::{Account, Mint},
+};
+
+use crate::{
+    account::get_token_account,
+    extension::MintExtensions,
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// A freshly created and initialized mint account.
+#[derive(Debug)]
+pub struct CreatedMint {
+    pub address: Pubkey,
+    pub signature: Signature,
+    pub state: Mint,
+    pub extensions: Vec<ExtensionType>,
+}
+
+/// Result of a `mint_to`, with the mint and destination state read back afterwards.
+#[derive(Debug)]
+pub struct MintedTokens {
+    pub signature: Signature,
+    pub mint: Mint,
+    pub account: Account,
+}
+
+/// Fetch and unpack a mint account of either token program.
+pub async fn get_mint(client: &RpcClient, address: &Pubkey) -> Result<Mint> {
+    let account = client.get_account(address).await?;
+    TokenProgram::from_owner(&account.owner)?;
+    Ok(StateWithExtensions::<Mint>::unpack(&account.data)?.base)
+}
+
+/// Create a new mint with `payer` as both mint and freeze authority.
+pub async fn create_mint(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedMint>> {
+    let extensions = MintExtensions::default();
+    create_mint_with_extensions(
+        client,
+        payer,
+        &Keypair::new(),
+        program,
+        decimals,
+        &extensions,
+        options,
+    )
+    .await
+}
+
+/// Create a new mint at the `mint` keypair's address, configuring Token-2022 `extensions` on it.
+///
+/// The account is sized and funded for the extensions, which are initialized before the mint.
+pub async fn create_mint_with_extensions(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    mint: &dyn Signer,
+    program: TokenProgram,
+    decimals: u8,
+    extensions: &MintExtensions,
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedMint>> {
+    let space = extensions.mint_len()?;
+    let funded_len = extensions.funded_len(&mint.pubkey(), &payer.pubkey())?;
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(funded_len)
+        .await?;
+
+    //Create the space, initialize the extensions, then the mint account type in that space.
+    //Metadata comes last since it needs an initialized mint, the rent above already covers it
+    let mut instructions = vec![create_account(
+        &payer.pubkey(),
+        &mint.pubkey(),
+        rent,
+        space as u64,
+        &program.id(),
+    )];
+    instructions.extend(extensions.instructions(program, &mint.pubkey())?);
+    instructions.push(initialize_mint(
+        &program.id(),
+        &mint.pubkey(),
+        &payer.pubkey(),
+        Some(&payer.pubkey()),
+        decimals,
+    )?);
+    if let Some(metadata) = &extensions.metadata {
+        instructions.extend(metadata.instructions(program, &mint.pubkey(), &payer.pubkey()));
+    }
+
+    send_instructions(
+        client,
+        &instructions,
+        &payer.pubkey(),
+        &[payer, mint],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        let account = client.get_account(&mint.pubkey()).await?;
+        let state = StateWithExtensions::<Mint>::unpack(&account.data)?;
+        Ok(CreatedMint {
+            address: mint.pubkey(),
+            signature,
+            state: state.base,
+            extensions: state.get_extension_types()?,
+        })
+    })
+    .await
+}
+
+/// Mint `amount` base units of `mint` into the `destination` token account.
+pub async fn mint_to(
+    client: &RpcClient,
+    mint_authority: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome<MintedTokens>> {
+    mint_to_with_signers(
+        client,
+        mint_authority,
+        program,
+        mint,
+        destination,
+        &mint_authority.pubkey(),
+        &[mint_authority],
+        amount,
+        options,
+    )
+    .await
+}
+
+/// Like [`mint_to`], for a mint authority that is a multisig approved by `signers`.
+///
+/// `payer` pays the fee and need not be one of the signers.
+#[allow(clippy::too_many_arguments)]
+pub async fn mint_to_with_signers(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    mint_authority: &Pubkey,
+    signers: &[&dyn Signer],
+    amount: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome<MintedTokens>> {
+    let signer_pubkeys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let instruction = mint_to_instruction(
+        &program.id(),
+        mint,
+        destination,
+        mint_authority,
+        &signer_pubkeys.iter().collect::<Vec<_>>(),
+        amount,
+    )?;
+
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    send_instructions(
+        client,
+        &[instruction],
+        &payer.pubkey(),
+        &all_signers,
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(MintedTokens {
+            signature,
+            mint: get_mint(client, mint).await?,
+            account: get_token_account(client, destination).await?,
+        })
+    })
+    .await
+}
@@ -0,0 +1,88 @@
+use anyhow::{Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::create_account;
+use spl_token_2022_interface::{
+    instruction::{MAX_SIGNERS, MIN_SIGNERS, initialize_multisig},
+    state::Multisig,
+};
+
+use crate::{
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// A freshly created m-of-n multisig account.
+#[derive(Debug)]
+pub struct CreatedMultisig {
+    pub address: Pubkey,
+    pub signature: Signature,
+    pub state: Multisig,
+}
+
+/// Fetch and unpack a multisig account of either token program.
+pub async fn get_multisig(client: &RpcClient, address: &Pubkey) -> Result<Multisig> {
+    let account = client.get_account(address).await?;
+    TokenProgram::from_owner(&account.owner)?;
+    Ok(Multisig::unpack(&account.data)?)
+}
+
+/// Create a multisig requiring `m` of `signers` to approve.
+///
+/// Its address can then be used as a mint authority or token account owner, with
+/// `mint_to_with_signers` and `transfer_checked_with_signers` collecting the signatures.
+pub async fn create_multisig(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    m: u8,
+    signers: &[Pubkey],
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedMultisig>> {
+    if !(MIN_SIGNERS..=MAX_SIGNERS).contains(&signers.len()) {
+        bail!("a multisig needs between {MIN_SIGNERS} and {MAX_SIGNERS} signers");
+    }
+    if m == 0 || m as usize > signers.len() {
+        bail!("m must be between 1 and the {} signers", signers.len());
+    }
+
+    let multisig = Keypair::new();
+    let space = Multisig::LEN;
+    let rent = client.get_minimum_balance_for_rent_exemption(space).await?;
+
+    //Create the space, then record the signers and threshold on it
+    let create_account_instruction = create_account(
+        &payer.pubkey(),
+        &multisig.pubkey(),
+        rent,
+        space as u64,
+        &program.id(),
+    );
+    let initialize_multisig_instruction = initialize_multisig(
+        &program.id(),
+        &multisig.pubkey(),
+        &signers.iter().collect::<Vec<_>>(),
+        m,
+    )?;
+
+    send_instructions(
+        client,
+        &[create_account_instruction, initialize_multisig_instruction],
+        &payer.pubkey(),
+        &[payer, &multisig],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(CreatedMultisig {
+            address: multisig.pubkey(),
+            signature,
+            state: get_multisig(client, &multisig.pubkey()).await?,
+        })
+    })
+    .await
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::{instruction::sync_native, state::Account};
+
+use crate::{
+    account::get_token_account,
+    close::{Closed, close_account},
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Result of a `wrap_sol`, with the wrapped SOL account read back afterwards.
+#[derive(Debug)]
+pub struct Wrapped {
+    pub signature: Signature,
+    pub address: Pubkey,
+    pub account: Account,
+}
+
+/// Move `lamports` from `owner` into its wrapped SOL ATA, creating the ATA when missing.
+///
+/// The balance becomes usable as tokens of [`TokenProgram::native_mint`], e.g. in
+/// `transfer_checked` with 9 decimals.
+pub async fn wrap_sol(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome<Wrapped>> {
+    let mint = program.native_mint();
+    let address = program.ata_address(&owner.pubkey(), &mint);
+
+    //Lamports sent to a native account only count as tokens once synced
+    let instructions = [
+        create_associated_token_account_idempotent(
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &mint,
+            &program.id(),
+        ),
+        transfer(&owner.pubkey(), &address, lamports),
+        sync_native(&program.id(), &address)?,
+    ];
+
+    send_instructions(client, &instructions, &owner.pubkey(), &[owner], options)
+        .await?
+        .and_then(|signature| async move {
+            Ok(Wrapped {
+                signature,
+                address,
+                account: get_token_account(client, &address).await?,
+            })
+        })
+        .await
+}
+
+/// Close `owner`'s wrapped SOL ATA, returning its balance and rent as SOL to `destination`.
+pub async fn unwrap_sol(
+    client: &RpcClient,
+    owner: &dyn Signer,
+    program: TokenProgram,
+    destination: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<Closed>> {
+    let address = program.ata_address(&owner.pubkey(), &program.native_mint());
+    close_account(client, owner, program, &address, destination, options).await
+}
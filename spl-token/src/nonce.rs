@@ -0,0 +1,158 @@
+use anyhow::Result;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    nonce_utils::{data_from_account, nonblocking::get_account_with_commitment},
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::{
+    advance_nonce_account, authorize_nonce_account as authorize_nonce_account_instruction,
+    create_nonce_account as create_nonce_account_instructions, withdraw_nonce_account,
+};
+use std::sync::Arc;
+
+use crate::transaction::{SendOptions, SendOutcome, send_instructions};
+
+/// Space of a nonce account.
+pub const NONCE_ACCOUNT_SIZE: usize = 80;
+
+/// A nonce account whose stored blockhash transactions are signed with instead of a recent
+/// one, so they stay valid until the nonce is advanced.
+#[derive(Clone)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Arc<dyn Signer + Send + Sync>,
+}
+
+impl DurableNonce {
+    /// The instruction that has to come first in a transaction using the nonce.
+    pub fn advance_instruction(&self) -> Instruction {
+        advance_nonce_account(&self.account, &self.authority.pubkey())
+    }
+}
+
+/// State of an initialized nonce account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceAccount {
+    pub authority: Pubkey,
+    /// The blockhash a transaction using the nonce is signed with.
+    pub nonce: Hash,
+    pub lamports_per_signature: u64,
+    pub lamports: u64,
+}
+
+/// A freshly created nonce account.
+#[derive(Debug)]
+pub struct CreatedNonceAccount {
+    pub address: Pubkey,
+    pub signature: Signature,
+    pub state: NonceAccount,
+}
+
+/// Fetch a nonce account at the client's commitment.
+pub async fn get_nonce_account(client: &RpcClient, address: &Pubkey) -> Result<NonceAccount> {
+    let account = get_account_with_commitment(client, address, client.commitment()).await?;
+    let data = data_from_account(&account)?;
+    Ok(NonceAccount {
+        authority: data.authority,
+        nonce: data.blockhash(),
+        lamports_per_signature: data.get_lamports_per_signature(),
+        lamports: account.lamports,
+    })
+}
+
+/// Create a rent-exempt nonce account advanced by `authority`.
+pub async fn create_nonce_account(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    authority: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<CreatedNonceAccount>> {
+    let nonce = Keypair::new();
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(NONCE_ACCOUNT_SIZE)
+        .await?;
+    let instructions =
+        create_nonce_account_instructions(&payer.pubkey(), &nonce.pubkey(), authority, rent);
+    let address = nonce.pubkey();
+    send_instructions(
+        client,
+        &instructions,
+        &payer.pubkey(),
+        &[payer, &nonce],
+        options,
+    )
+    .await?
+    .and_then(|signature| async move {
+        Ok(CreatedNonceAccount {
+            address,
+            signature,
+            state: get_nonce_account(client, &address).await?,
+        })
+    })
+    .await
+}
+
+/// Store a new nonce, invalidating transactions signed with the current one.
+pub async fn advance_nonce(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    address: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome<NonceAccount>> {
+    let instruction = advance_nonce_account(address, &authority.pubkey());
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await?
+    .and_then(|_| get_nonce_account(client, address))
+    .await
+}
+
+/// Hand the nonce account over to `new_authority`.
+pub async fn authorize_nonce_account(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    address: &Pubkey,
+    new_authority: &Pubkey,
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    let instruction =
+        authorize_nonce_account_instruction(address, &authority.pubkey(), new_authority);
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await
+}
+
+/// Move lamports out of a nonce account, closing it when all of them are withdrawn.
+pub async fn withdraw_from_nonce_account(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    address: &Pubkey,
+    destination: &Pubkey,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    let instruction = withdraw_nonce_account(address, &authority.pubkey(), destination, lamports);
+    send_instructions(
+        client,
+        &[instruction],
+        &authority.pubkey(),
+        &[authority],
+        options,
+    )
+    .await
+}
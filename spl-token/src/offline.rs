@@ -0,0 +1,192 @@
+use anyhow::{Context, Result, bail};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use std::{fmt, str::FromStr};
+
+use crate::transaction::{RecentBlockhash, send_until_expired};
+
+/// How an exported transaction is written out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The serialized transaction, base64 encoded.
+    #[default]
+    Base64,
+    /// The base64 transaction along with its blockhash and who has signed it.
+    Json,
+}
+
+/// A signed or partially signed transaction, carried to the signers it still needs and then
+/// to [`broadcast`].
+#[derive(Debug, Clone)]
+pub struct ExportedTransaction {
+    pub transaction: VersionedTransaction,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedJson {
+    transaction: String,
+    #[serde(default)]
+    blockhash: String,
+    #[serde(default)]
+    signers: Vec<SignerJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignerJson {
+    pubkey: String,
+    signed: bool,
+}
+
+impl ExportedTransaction {
+    /// The accounts that have to sign, in signature order.
+    pub fn signers(&self) -> &[Pubkey] {
+        let required = self.transaction.message.header().num_required_signatures;
+        &self.transaction.message.static_account_keys()[..usize::from(required)]
+    }
+
+    /// Signers whose signature is still missing.
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.signers()
+            .iter()
+            .zip(&self.transaction.signatures)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(signer, _)| *signer)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    /// Add the signatures of `signers`, each of which must be one the transaction needs.
+    pub fn sign(&mut self, signers: &[&dyn Signer]) -> Result<()> {
+        let message = self.transaction.message.serialize();
+        for signer in signers {
+            let pubkey = signer.try_pubkey()?;
+            let index = self
+                .signers()
+                .iter()
+                .position(|required| *required == pubkey)
+                .with_context(|| format!("{pubkey} is not a signer of the transaction"))?;
+            self.transaction.signatures[index] = signer.try_sign_message(&message)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_base64(&self) -> Result<String> {
+        Ok(BASE64_STANDARD.encode(bincode::serialize(&self.transaction)?))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let missing = self.missing_signers();
+        let json = ExportedJson {
+            transaction: self.to_base64()?,
+            blockhash: self.transaction.message.recent_blockhash().to_string(),
+            signers: self
+                .signers()
+                .iter()
+                .map(|signer| SignerJson {
+                    pubkey: signer.to_string(),
+                    signed: !missing.contains(signer),
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    pub fn export(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Base64 => self.to_base64(),
+            ExportFormat::Json => self.to_json(),
+        }
+    }
+
+    /// Read a transaction written by [`ExportedTransaction::export`] in either format.
+    pub fn parse(exported: &str) -> Result<Self> {
+        let exported = exported.trim();
+        let encoded = if exported.starts_with('{') {
+            serde_json::from_str::<ExportedJson>(exported)
+                .context("invalid exported transaction JSON")?
+                .transaction
+        } else {
+            exported.to_string()
+        };
+        let bytes = BASE64_STANDARD
+            .decode(encoded.trim())
+            .context("exported transaction is not valid base64")?;
+        let transaction: VersionedTransaction =
+            bincode::deserialize(&bytes).context("not a serialized transaction")?;
+        if transaction.signatures.len()
+            != usize::from(transaction.message.header().num_required_signatures)
+        {
+            bail!("the transaction carries the wrong number of signatures");
+        }
+        Ok(Self { transaction })
+    }
+}
+
+impl fmt::Display for ExportedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing = self.missing_signers();
+        if missing.is_empty() {
+            return f.write_str("transaction signed, nothing was sent");
+        }
+        let missing: Vec<String> = missing.iter().map(Pubkey::to_string).collect();
+        write!(
+            f,
+            "transaction partially signed, missing the signatures of {}",
+            missing.join(", ")
+        )
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "base64" => Ok(ExportFormat::Base64),
+            "json" => Ok(ExportFormat::Json),
+            other => bail!("unknown export format `{other}`, expected base64 or json"),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Base64 => f.write_str("base64"),
+            ExportFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+/// Send a fully signed transaction and wait for it to land.
+///
+/// It is rebroadcast until its blockhash expires, or the nonce it was signed with advances.
+pub async fn broadcast(client: &RpcClient, exported: &ExportedTransaction) -> Result<Signature> {
+    if !exported.is_complete() {
+        bail!("{exported}");
+    }
+    let invalid: Vec<String> = exported
+        .signers()
+        .iter()
+        .zip(exported.transaction.verify_with_results())
+        .filter(|(_, valid)| !valid)
+        .map(|(signer, _)| signer.to_string())
+        .collect();
+    if !invalid.is_empty() {
+        bail!("invalid signatures of {}", invalid.join(", "));
+    }
+    let blockhash = RecentBlockhash::of_transaction(client, &exported.transaction).await?;
+    match send_until_expired(client, &exported.transaction, &blockhash).await? {
+        Some(signature) => Ok(signature),
+        None => bail!("transaction expired unconfirmed, it has to be signed again"),
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::{Result, bail};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account_interface::address::get_associated_token_address_with_program_id;
+use std::{fmt, str::FromStr};
+
+/// Which token program owns a mint and its token accounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenProgram {
+    #[default]
+    Token,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token_interface::id(),
+            TokenProgram::Token2022 => spl_token_2022_interface::id(),
+        }
+    }
+
+    /// The mint of wrapped SOL for this program.
+    pub fn native_mint(&self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token_interface::native_mint::id(),
+            TokenProgram::Token2022 => spl_token_2022_interface::native_mint::id(),
+        }
+    }
+
+    /// The program owning an account, or an error when it is not a token program.
+    pub fn from_owner(owner: &Pubkey) -> Result<Self> {
+        if *owner == spl_token_interface::id() {
+            Ok(TokenProgram::Token)
+        } else if *owner == spl_token_2022_interface::id() {
+            Ok(TokenProgram::Token2022)
+        } else {
+            bail!("account is owned by {owner}, which is not a token program")
+        }
+    }
+
+    /// Look up the program owning an existing mint.
+    pub async fn of_mint(client: &RpcClient, mint: &Pubkey) -> Result<Self> {
+        Self::from_owner(&client.get_account(mint).await?.owner)
+    }
+
+    /// The associated token account of `owner` for a mint of this program.
+    pub fn ata_address(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.id())
+    }
+}
+
+impl FromStr for TokenProgram {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "token" | "spl-token" => Ok(TokenProgram::Token),
+            "token-2022" | "token2022" | "spl-token-2022" => Ok(TokenProgram::Token2022),
+            other => match other.parse::<Pubkey>() {
+                Ok(id) => Self::from_owner(&id),
+                Err(_) => bail!("unknown token program `{other}`, expected token or token-2022"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for TokenProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenProgram::Token => f.write_str("token"),
+            TokenProgram::Token2022 => f.write_str("token-2022"),
+        }
+    }
+}
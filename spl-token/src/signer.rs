@@ -0,0 +1,443 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use solana_derivation_path::DerivationPath;
+use solana_keypair::seed_derivable::keypair_from_seed_and_derivation_path;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer, read_keypair_file},
+    signer::null_signer::NullSigner,
+};
+use solana_seed_phrase::generate_seed_from_seed_phrase_and_passphrase;
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// Keypair spec used when no `--keypair` flag is given.
+pub const KEYPAIR_ENV: &str = "SPL_TOKEN_KEYPAIR";
+/// Legacy keypair file path read from `.env`.
+pub const WALLET_PATH_ENV: &str = "WALLET_PATH";
+
+/// Where a keypair is loaded from.
+///
+/// Parsed from a spec string:
+/// - `<path>` or `file:<path>` — keypair JSON file written by `solana-keygen`
+/// - `config` or `config:<path>` — `keypair_path` of the Solana CLI `config.yml`
+/// - `env:<VAR>` — base58 encoded 64 byte secret key stored in `VAR`
+/// - `mnemonic:<VAR>` or `mnemonic:<VAR>:<derivation path>` — BIP39 seed phrase stored in
+///   `VAR`, with an optional passphrase in `<VAR>_PASSPHRASE`. The derivation path is either
+///   absolute (`m/44'/501'/0'/0'`) or `<account>/<change>` under the Solana BIP44 root.
+/// - `pubkey:<ADDRESS>` — a signer whose key is elsewhere, its signature left out of
+///   transactions exported in sign-only mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeypairSource {
+    File(PathBuf),
+    SolanaConfig(Option<PathBuf>),
+    Base58Env(String),
+    Mnemonic {
+        phrase_env: String,
+        derivation_path: Option<String>,
+    },
+    Pubkey(Pubkey),
+}
+
+impl KeypairSource {
+    /// `SPL_TOKEN_KEYPAIR`, then `WALLET_PATH` (also read from `.env`), then the Solana CLI
+    /// default keypair.
+    pub fn from_env() -> Result<Self> {
+        dotenvy::dotenv().ok();
+        if let Ok(spec) = env::var(KEYPAIR_ENV) {
+            return spec.parse();
+        }
+        if let Ok(path) = env::var(WALLET_PATH_ENV) {
+            return Ok(KeypairSource::File(path.into()));
+        }
+        Ok(KeypairSource::SolanaConfig(None))
+    }
+
+    pub fn load(&self) -> Result<Keypair> {
+        match self {
+            KeypairSource::File(path) => load_keypair_file(path),
+            KeypairSource::SolanaConfig(config_path) => {
+                let config_path = match config_path {
+                    Some(path) => path.clone(),
+                    None => default_solana_config_path()?,
+                };
+                let keypair_path = solana_config_keypair_path(&config_path)?;
+                load_keypair_file(&keypair_path).with_context(|| {
+                    format!(
+                        "failed to load the default keypair of {}",
+                        config_path.display()
+                    )
+                })
+            }
+            KeypairSource::Base58Env(var) => {
+                let secret = env::var(var).with_context(|| format!("{var} is not set"))?;
+                keypair_from_base58(secret.trim())
+                    .with_context(|| format!("invalid secret in {var}"))
+            }
+            KeypairSource::Mnemonic {
+                phrase_env,
+                derivation_path,
+            } => {
+                let phrase =
+                    env::var(phrase_env).with_context(|| format!("{phrase_env} is not set"))?;
+                let passphrase = env::var(format!("{phrase_env}_PASSPHRASE")).unwrap_or_default();
+                keypair_from_mnemonic(&phrase, &passphrase, derivation_path.as_deref())
+                    .with_context(|| format!("invalid seed phrase in {phrase_env}"))
+            }
+            KeypairSource::Pubkey(pubkey) => bail!("{pubkey} is only a pubkey, not a keypair"),
+        }
+    }
+
+    /// The keypair, or for `pubkey:` a signer leaving its signature out, which is only allowed
+    /// when `sign_only`, since nothing sending the transaction could use it.
+    pub fn signer(&self, sign_only: bool) -> Result<Arc<dyn Signer + Send + Sync>> {
+        match self {
+            KeypairSource::Pubkey(pubkey) if sign_only => Ok(Arc::new(NullSigner::new(pubkey))),
+            KeypairSource::Pubkey(pubkey) => {
+                bail!("{pubkey} can only stand in for a signer with --sign-only")
+            }
+            _ => Ok(Arc::new(self.load()?)),
+        }
+    }
+}
+
+impl FromStr for KeypairSource {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let source = match spec.split_once(':') {
+            Some(("file", path)) => KeypairSource::File(path.into()),
+            Some(("config", path)) => KeypairSource::SolanaConfig(Some(path.into())),
+            Some(("env", var)) => KeypairSource::Base58Env(var.to_string()),
+            Some(("pubkey", pubkey)) => KeypairSource::Pubkey(
+                pubkey
+                    .parse()
+                    .with_context(|| format!("invalid pubkey in keypair spec `{spec}`"))?,
+            ),
+            Some(("mnemonic", rest)) => match rest.split_once(':') {
+                Some((var, path)) => KeypairSource::Mnemonic {
+                    phrase_env: var.to_string(),
+                    derivation_path: Some(path.to_string()),
+                },
+                None => KeypairSource::Mnemonic {
+                    phrase_env: rest.to_string(),
+                    derivation_path: None,
+                },
+            },
+            _ if spec == "config" => KeypairSource::SolanaConfig(None),
+            _ => KeypairSource::File(spec.into()),
+        };
+        match &source {
+            KeypairSource::File(path) if path.as_os_str().is_empty() => {
+                bail!("empty keypair path")
+            }
+            KeypairSource::Base58Env(var)
+            | KeypairSource::Mnemonic {
+                phrase_env: var, ..
+            } if var.is_empty() => {
+                bail!("missing environment variable name in keypair spec `{spec}`")
+            }
+            _ => Ok(source),
+        }
+    }
+}
+
+impl fmt::Display for KeypairSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            //Spelled out when the bare path would read as another source
+            KeypairSource::File(path)
+                if path == Path::new("config") || path.to_string_lossy().contains(':') =>
+            {
+                write!(f, "file:{}", path.display())
+            }
+            KeypairSource::File(path) => write!(f, "{}", path.display()),
+            KeypairSource::SolanaConfig(None) => f.write_str("config"),
+            KeypairSource::SolanaConfig(Some(path)) => write!(f, "config:{}", path.display()),
+            KeypairSource::Base58Env(var) => write!(f, "env:{var}"),
+            KeypairSource::Mnemonic {
+                phrase_env,
+                derivation_path: None,
+            } => write!(f, "mnemonic:{phrase_env}"),
+            KeypairSource::Mnemonic {
+                phrase_env,
+                derivation_path: Some(path),
+            } => write!(f, "mnemonic:{phrase_env}:{path}"),
+            KeypairSource::Pubkey(pubkey) => write!(f, "pubkey:{pubkey}"),
+        }
+    }
+}
+
+/// Read a keypair JSON file (the format written by `solana-keygen`).
+pub fn load_keypair(path: &str) -> Result<Keypair> {
+    load_keypair_file(&PathBuf::from(path))
+}
+
+/// Load the keypair selected by the environment, see [`KeypairSource::from_env`].
+pub fn load_default_keypair() -> Result<Keypair> {
+    let source = KeypairSource::from_env()?;
+    source
+        .load()
+        .with_context(|| format!("failed to load keypair `{source}`"))
+}
+
+pub fn keypair_from_base58(secret: &str) -> Result<Keypair> {
+    let bytes = bs58::decode(secret)
+        .into_vec()
+        .context("secret key is not valid base58")?;
+    Keypair::try_from(bytes.as_slice()).map_err(|err| {
+        anyhow!(
+            "expected a 64 byte secret key, got {} bytes: {err}",
+            bytes.len()
+        )
+    })
+}
+
+/// Derive a keypair from a BIP39 seed phrase.
+///
+/// Without a derivation path the keypair comes straight from the seed, which is what
+/// `solana-keygen recover` does by default.
+pub fn keypair_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: Option<&str>,
+) -> Result<Keypair> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    bip39::Mnemonic::parse_normalized(&phrase).context("not a valid BIP39 mnemonic")?;
+    let seed = generate_seed_from_seed_phrase_and_passphrase(&phrase, passphrase);
+
+    let derivation_path = derivation_path
+        .map(|path| {
+            if path.starts_with('m') {
+                DerivationPath::from_absolute_path_str(path)
+            } else {
+                DerivationPath::from_key_str(path)
+            }
+            .map_err(|err| anyhow!("invalid derivation path `{path}`: {err}"))
+        })
+        .transpose()?;
+    match derivation_path {
+        Some(path) => keypair_from_seed_and_derivation_path(&seed, Some(path)),
+        None => solana_keypair::keypair_from_seed(&seed),
+    }
+    .map_err(|err| anyhow!("{err}"))
+}
+
+#[derive(Deserialize)]
+struct SolanaCliConfig {
+    keypair_path: String,
+}
+
+fn default_solana_config_path() -> Result<PathBuf> {
+    let home = env::var("HOME").context("HOME is not set, cannot locate the Solana CLI config")?;
+    Ok(PathBuf::from(home).join(".config/solana/cli/config.yml"))
+}
+
+fn solana_config_keypair_path(config_path: &PathBuf) -> Result<PathBuf> {
+    let contents = fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read Solana CLI config {}", config_path.display()))?;
+    let config: SolanaCliConfig = serde_yaml::from_str(&contents).with_context(|| {
+        format!(
+            "failed to parse Solana CLI config {}",
+            config_path.display()
+        )
+    })?;
+    Ok(match config.keypair_path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(config.keypair_path),
+    })
+}
+
+fn load_keypair_file(path: &PathBuf) -> Result<Keypair> {
+    if !path.exists() {
+        bail!("keypair file {} does not exist", path.display());
+    }
+    read_keypair_file(path)
+        .map_err(|err| anyhow!("failed to read keypair from {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::write_keypair_file;
+
+    /// The BIP39 test mnemonic, with pubkeys derived from it independently of this crate.
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                            abandon abandon abandon about";
+    const SEED_PUBKEY: &str = "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o";
+    const ACCOUNT_0_PUBKEY: &str = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+    const ACCOUNT_1_PUBKEY: &str = "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb";
+    const PASSPHRASE_PUBKEY: &str = "6UChi37U4BGomEQR665JZNjTM2PiHcUmBCD3e7TD366v";
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("spl-token-signer-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn parses_every_spec() {
+        let cases = [
+            ("id.json", KeypairSource::File("id.json".into())),
+            ("file:config", KeypairSource::File("config".into())),
+            (
+                "file:keys:id.json",
+                KeypairSource::File("keys:id.json".into()),
+            ),
+            ("config", KeypairSource::SolanaConfig(None)),
+            (
+                "config:/etc/solana.yml",
+                KeypairSource::SolanaConfig(Some("/etc/solana.yml".into())),
+            ),
+            ("env:SECRET", KeypairSource::Base58Env("SECRET".to_string())),
+            (
+                "mnemonic:PHRASE",
+                KeypairSource::Mnemonic {
+                    phrase_env: "PHRASE".to_string(),
+                    derivation_path: None,
+                },
+            ),
+            (
+                "mnemonic:PHRASE:m/44'/501'/0'/0'",
+                KeypairSource::Mnemonic {
+                    phrase_env: "PHRASE".to_string(),
+                    derivation_path: Some("m/44'/501'/0'/0'".to_string()),
+                },
+            ),
+            (
+                "pubkey:11111111111111111111111111111111",
+                KeypairSource::Pubkey(Pubkey::default()),
+            ),
+        ];
+        for (spec, expected) in cases {
+            let source: KeypairSource = spec.parse().unwrap();
+            assert_eq!(source, expected, "{spec}");
+            //Display writes a spec that parses back to the same source
+            assert_eq!(source.to_string().parse::<KeypairSource>().unwrap(), source);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        for spec in [
+            "",
+            "file:",
+            "env:",
+            "mnemonic:",
+            "pubkey:",
+            "pubkey:not-a-pubkey",
+        ] {
+            assert!(spec.parse::<KeypairSource>().is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn loads_files_and_the_solana_config() {
+        let keypair = Keypair::new();
+        let keypair_path = temp_path("id.json");
+        write_keypair_file(&keypair, &keypair_path).unwrap();
+        let config_path = temp_path("config.yml");
+        fs::write(
+            &config_path,
+            format!("keypair_path: {}\n", keypair_path.display()),
+        )
+        .unwrap();
+
+        let from_file = KeypairSource::File(keypair_path.clone()).load().unwrap();
+        let from_config = KeypairSource::SolanaConfig(Some(config_path.clone()))
+            .load()
+            .unwrap();
+        let missing = KeypairSource::File(temp_path("missing.json")).load();
+        fs::remove_file(keypair_path).unwrap();
+        fs::remove_file(config_path).unwrap();
+
+        assert_eq!(from_file.pubkey(), keypair.pubkey());
+        assert_eq!(from_config.pubkey(), keypair.pubkey());
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn loads_base58_secrets() {
+        let keypair = Keypair::new();
+        assert_eq!(
+            keypair_from_base58(&keypair.to_base58_string())
+                .unwrap()
+                .pubkey(),
+            keypair.pubkey()
+        );
+        assert!(keypair_from_base58("not base58 0OIl").is_err());
+        assert!(keypair_from_base58(&bs58::encode([1u8; 32]).into_string()).is_err());
+
+        //Variable names unique to this test
+        unsafe { env::set_var("SPL_TOKEN_TEST_SECRET", keypair.to_base58_string()) };
+        let source: KeypairSource = "env:SPL_TOKEN_TEST_SECRET".parse().unwrap();
+        assert_eq!(source.load().unwrap().pubkey(), keypair.pubkey());
+        let unset: KeypairSource = "env:SPL_TOKEN_TEST_UNSET".parse().unwrap();
+        assert!(unset.load().is_err());
+    }
+
+    #[test]
+    fn derives_known_mnemonic_vectors() {
+        let derive = |passphrase, path| {
+            keypair_from_mnemonic(MNEMONIC, passphrase, path)
+                .unwrap()
+                .pubkey()
+                .to_string()
+        };
+        assert_eq!(derive("", None), SEED_PUBKEY);
+        assert_eq!(derive("", Some("m/44'/501'/0'/0'")), ACCOUNT_0_PUBKEY);
+        assert_eq!(derive("", Some("0/0")), ACCOUNT_0_PUBKEY);
+        assert_eq!(derive("", Some("1/0")), ACCOUNT_1_PUBKEY);
+        assert_eq!(derive("TREZOR", None), PASSPHRASE_PUBKEY);
+        //Extra whitespace is normalised away
+        assert_eq!(
+            keypair_from_mnemonic(&format!("  {}\n", MNEMONIC.replace(' ', "   ")), "", None)
+                .unwrap()
+                .pubkey()
+                .to_string(),
+            SEED_PUBKEY
+        );
+
+        assert!(keypair_from_mnemonic("abandon abandon abandon", "", None).is_err());
+        assert!(keypair_from_mnemonic(MNEMONIC, "", Some("m/not/a/path")).is_err());
+    }
+
+    #[test]
+    fn loads_mnemonics_with_their_passphrase() {
+        //Variable names unique to this test
+        unsafe {
+            env::set_var("SPL_TOKEN_TEST_PHRASE", MNEMONIC);
+            env::set_var("SPL_TOKEN_TEST_PHRASE_PASSPHRASE", "TREZOR");
+            env::set_var("SPL_TOKEN_TEST_PLAIN_PHRASE", MNEMONIC);
+        }
+        let with_passphrase: KeypairSource = "mnemonic:SPL_TOKEN_TEST_PHRASE".parse().unwrap();
+        let derived: KeypairSource = "mnemonic:SPL_TOKEN_TEST_PLAIN_PHRASE:1/0".parse().unwrap();
+        assert_eq!(
+            with_passphrase.load().unwrap().pubkey().to_string(),
+            PASSPHRASE_PUBKEY
+        );
+        assert_eq!(
+            derived.load().unwrap().pubkey().to_string(),
+            ACCOUNT_1_PUBKEY
+        );
+    }
+
+    #[test]
+    fn pubkeys_have_no_keypair() {
+        let pubkey = Pubkey::new_unique();
+        let source = KeypairSource::Pubkey(pubkey);
+        assert!(source.load().is_err());
+        //Outside sign-only mode nothing could use a signer without a key
+        assert!(source.signer(false).is_err());
+        assert_eq!(source.signer(true).unwrap().pubkey(), pubkey);
+
+        let keypair = Keypair::new();
+        let path = temp_path("signer.json");
+        write_keypair_file(&keypair, &path).unwrap();
+        let signer = KeypairSource::File(path.clone()).signer(false);
+        fs::remove_file(path).unwrap();
+        assert_eq!(signer.unwrap().pubkey(), keypair.pubkey());
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    account::Account as SolanaAccount, instruction::Instruction,
+    message::AddressLookupTableAccount, pubkey::Pubkey, signature::Signer,
+    transaction::TransactionError,
+};
+use spl_token_2022_interface::{
+    extension::StateWithExtensions,
+    state::{Account, Mint},
+};
+use std::{collections::BTreeSet, fmt};
+
+use crate::{
+    compute_budget::MAX_COMPUTE_UNIT_LIMIT, program::TokenProgram, transaction::sign_transaction,
+};
+
+/// What a transaction would do, from simulating it against the current cluster state.
+///
+/// Also the outcome of any operation run in [`SendMode::Simulate`](crate::SendMode::Simulate),
+/// since nothing it would read back afterwards exists.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// Token accounts and mints the transaction writes, as they would be afterwards.
+    pub accounts: Vec<(Pubkey, PostState)>,
+}
+
+/// State of a written token program account after a simulated transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostState {
+    TokenAccount(Account),
+    Mint(Mint),
+    Closed,
+}
+
+impl Simulation {
+    pub fn succeeded(&self) -> bool {
+        self.err.is_none()
+    }
+
+    /// A mint written by the transaction, as it would be afterwards.
+    pub fn mint(&self, address: &Pubkey) -> Option<&Mint> {
+        self.accounts
+            .iter()
+            .find_map(|(account, state)| match state {
+                PostState::Mint(mint) if account == address => Some(mint),
+                _ => None,
+            })
+    }
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.err {
+            Some(err) => write!(f, "transaction simulation failed: {err}"),
+            None => f.write_str("transaction simulated, nothing was sent"),
+        }
+    }
+}
+
+/// Simulate a transaction carrying `instructions`, signed by `signers`, without the unit limit
+/// getting in the way.
+pub async fn simulate(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+) -> Result<Simulation> {
+    simulate_with_lookup_tables(client, instructions, payer, signers, &[]).await
+}
+
+/// Like [`simulate`], for a v0 transaction using `lookup_tables`.
+pub async fn simulate_with_lookup_tables(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Simulation> {
+    let instructions = [
+        vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            MAX_COMPUTE_UNIT_LIMIT,
+        )],
+        instructions.to_vec(),
+    ]
+    .concat();
+    let writable: BTreeSet<Pubkey> = instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    let writable: Vec<Pubkey> = writable.into_iter().collect();
+    //Accounts closed by the transaction are only recognisable from their current owner
+    let before = client.get_multiple_accounts(&writable).await?;
+
+    let blockhash = client.get_latest_blockhash().await?;
+    let transaction = sign_transaction(&instructions, payer, signers, blockhash, lookup_tables)?;
+    let config = RpcSimulateTransactionConfig {
+        commitment: Some(client.commitment()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: writable.iter().map(Pubkey::to_string).collect(),
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = client
+        .simulate_transaction_with_config(&transaction, config)
+        .await?
+        .value;
+
+    let after = result.accounts.unwrap_or_default();
+    let accounts = writable
+        .into_iter()
+        .zip(before)
+        .zip(after)
+        .filter_map(|((address, before), after)| {
+            let after = after.and_then(|account| account.decode::<SolanaAccount>());
+            let state = match after {
+                Some(account) => post_state(&account)?,
+                None if TokenProgram::from_owner(&before?.owner).is_ok() => PostState::Closed,
+                None => return None,
+            };
+            Some((address, state))
+        })
+        .collect();
+
+    Ok(Simulation {
+        err: result.err.map(Into::into),
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        accounts,
+    })
+}
+
+fn post_state(account: &SolanaAccount) -> Option<PostState> {
+    TokenProgram::from_owner(&account.owner).ok()?;
+    if account.lamports == 0 {
+        return Some(PostState::Closed);
+    }
+    if let Ok(state) = StateWithExtensions::<Account>::unpack(&account.data) {
+        return Some(PostState::TokenAccount(state.base));
+    }
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .ok()
+        .map(|state| PostState::Mint(state.base))
+}
@@ -0,0 +1,425 @@
+use anyhow::{Result, bail};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::{AddressLookupTableAccount, Message, VersionedMessage, v0},
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::{Result as TransactionResult, VersionedTransaction},
+};
+use solana_system_interface::instruction::advance_nonce_account;
+use std::{future::Future, time::Duration};
+use tokio::time::sleep;
+
+use crate::{
+    compute_budget::ComputeBudget,
+    nonce::{DurableNonce, get_nonce_account},
+    offline::ExportedTransaction,
+    simulate::{Simulation, simulate_with_lookup_tables},
+};
+
+/// Times a transaction is signed with a new blockhash before giving up.
+pub const MAX_SEND_ATTEMPTS: usize = 5;
+
+/// Blocks a recent blockhash stays valid for.
+pub const MAX_PROCESSING_AGE: u64 = 150;
+
+/// How often the status of a sent transaction is checked, and the transaction rebroadcast.
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the library builds and sends the transactions of one call.
+#[derive(Clone, Default)]
+pub struct SendOptions {
+    /// Compute budget instructions put in front of every transaction.
+    pub compute_budget: ComputeBudget,
+    /// Sign with this nonce instead of a recent blockhash, except in transactions writing
+    /// the nonce account themselves.
+    pub nonce: Option<DurableNonce>,
+    /// Whether the transactions are sent at all.
+    pub mode: SendMode,
+}
+
+/// What becomes of the transactions of a call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendMode {
+    /// Send them and wait for confirmation.
+    #[default]
+    Send,
+    /// Only simulate them, nothing is sent.
+    Simulate,
+    /// Sign them with the signers at hand and export them, nothing is sent.
+    SignOnly,
+}
+
+impl SendMode {
+    /// Fail unless every one of the `transactions` a call needs is sent: only a single one can
+    /// be simulated or exported, since the next ones build on what it would have done.
+    pub(crate) fn ensure_single(self, transactions: usize) -> Result<()> {
+        let unsent = match self {
+            SendMode::Send => return Ok(()),
+            SendMode::Simulate => "simulated",
+            SendMode::SignOnly => "exported",
+        };
+        if transactions > 1 {
+            bail!("this takes {transactions} transactions, only a single one can be {unsent}");
+        }
+        Ok(())
+    }
+}
+
+/// The result of a call, depending on its [`SendMode`].
+#[derive(Debug)]
+pub enum SendOutcome<T = Signature> {
+    /// The transaction was sent, with what the call read back afterwards.
+    Sent(T),
+    /// The transaction was only simulated.
+    Simulated(Simulation),
+    /// The transaction was signed and exported, to be signed further or broadcast later.
+    Exported(ExportedTransaction),
+}
+
+impl<T> SendOutcome<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> SendOutcome<U> {
+        match self {
+            SendOutcome::Sent(sent) => SendOutcome::Sent(f(sent)),
+            SendOutcome::Simulated(simulation) => SendOutcome::Simulated(simulation),
+            SendOutcome::Exported(exported) => SendOutcome::Exported(exported),
+        }
+    }
+
+    /// Carry on with `f` once the transaction was sent, passing any other outcome through.
+    pub async fn and_then<U, F, Fut>(self, f: F) -> Result<SendOutcome<U>>
+    where
+        F: FnOnce(T) -> Fut,
+        Fut: Future<Output = Result<U>>,
+    {
+        match self {
+            SendOutcome::Sent(sent) => Ok(SendOutcome::Sent(f(sent).await?)),
+            SendOutcome::Simulated(simulation) => Ok(SendOutcome::Simulated(simulation)),
+            SendOutcome::Exported(exported) => Ok(SendOutcome::Exported(exported)),
+        }
+    }
+
+    /// What the sent transaction produced, failing when it was not sent.
+    pub fn sent(self) -> Result<T> {
+        match self {
+            SendOutcome::Sent(sent) => Ok(sent),
+            SendOutcome::Simulated(simulation) => bail!("{simulation}"),
+            SendOutcome::Exported(exported) => bail!("{exported}"),
+        }
+    }
+}
+
+/// A blockhash and the last block height a transaction using it can land in.
+#[derive(Debug, Clone, Copy)]
+pub struct RecentBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    /// The nonce account the blockhash was read from, valid until the nonce advances
+    /// whatever the block height.
+    pub nonce_account: Option<Pubkey>,
+}
+
+impl RecentBlockhash {
+    /// The latest blockhash at the client's commitment.
+    pub async fn fetch(client: &RpcClient) -> Result<Self> {
+        let (blockhash, last_valid_block_height) = client
+            .get_latest_blockhash_with_commitment(client.commitment())
+            .await?;
+        Ok(Self {
+            blockhash,
+            last_valid_block_height,
+            nonce_account: None,
+        })
+    }
+
+    /// The nonce currently stored in `nonce_account`.
+    pub async fn from_nonce(client: &RpcClient, nonce_account: &Pubkey) -> Result<Self> {
+        Ok(Self {
+            blockhash: get_nonce_account(client, nonce_account).await?.nonce,
+            last_valid_block_height: u64::MAX,
+            nonce_account: Some(*nonce_account),
+        })
+    }
+
+    /// The blockhash `transaction` was signed with: the nonce it advances, or a recent
+    /// blockhash, which is valid for [`MAX_PROCESSING_AGE`] more blocks at most.
+    pub async fn of_transaction(
+        client: &RpcClient,
+        transaction: &VersionedTransaction,
+    ) -> Result<Self> {
+        let message = &transaction.message;
+        let blockhash = *message.recent_blockhash();
+        let keys = message.static_account_keys();
+        let advance = advance_nonce_account(&Pubkey::default(), &Pubkey::default());
+        let nonce_account = message.instructions().first().and_then(|instruction| {
+            (keys.get(usize::from(instruction.program_id_index)) == Some(&advance.program_id)
+                && instruction.data == advance.data)
+                .then(|| keys.get(usize::from(*instruction.accounts.first()?)))
+                .flatten()
+        });
+        if let Some(nonce_account) = nonce_account {
+            return Ok(Self {
+                blockhash,
+                last_valid_block_height: u64::MAX,
+                nonce_account: Some(*nonce_account),
+            });
+        }
+        if !client
+            .is_blockhash_valid(&blockhash, client.commitment())
+            .await?
+        {
+            bail!(
+                "blockhash {blockhash} of the transaction has expired, it has to be signed again"
+            );
+        }
+        Ok(Self {
+            blockhash,
+            last_valid_block_height: client.get_block_height().await? + MAX_PROCESSING_AGE,
+            nonce_account: None,
+        })
+    }
+
+    /// The stored nonce when there is one, the latest blockhash otherwise.
+    pub(crate) async fn fetch_for(
+        client: &RpcClient,
+        nonce: Option<&DurableNonce>,
+    ) -> Result<Self> {
+        match nonce {
+            Some(nonce) => Self::from_nonce(client, &nonce.account).await,
+            None => Self::fetch(client).await,
+        }
+    }
+
+    /// Whether the cluster is past the last block a transaction with this blockhash can land in,
+    /// or the nonce it was read from has advanced since.
+    pub async fn is_expired(&self, client: &RpcClient) -> Result<bool> {
+        match self.nonce_account {
+            Some(account) => Ok(get_nonce_account(client, &account).await?.nonce != self.blockhash),
+            None => Ok(client.get_block_height().await? > self.last_valid_block_height),
+        }
+    }
+}
+
+/// Sign `instructions` with a fresh blockhash, send them and wait for confirmation.
+///
+/// The compute budget instructions of `options` are put in front of them. When the blockhash
+/// expires before the transaction lands it is signed again with a new one and resent, up to
+/// [`MAX_SEND_ATTEMPTS`] times.
+///
+/// With a [`DurableNonce`] in `options` the transaction advances it and is signed with the stored
+/// nonce instead, along with the nonce authority.
+///
+/// In [`SendMode::Simulate`] nothing is sent and the [`Simulation`] is returned instead, in
+/// [`SendMode::SignOnly`] the [`ExportedTransaction`].
+pub async fn send_instructions(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    send_instructions_with_lookup_tables(client, instructions, payer, signers, &[], options).await
+}
+
+/// Like [`send_instructions`], in a v0 transaction loading the accounts found in
+/// `lookup_tables` from them. Without tables a legacy transaction is sent.
+pub async fn send_instructions_with_lookup_tables(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+    options: &SendOptions,
+) -> Result<SendOutcome> {
+    let nonce = durable_nonce(instructions, options);
+    let signers = nonce_signers(signers, nonce);
+    if options.mode == SendMode::Simulate {
+        let advance = nonce.map(DurableNonce::advance_instruction);
+        let instructions = [advance.as_slice(), instructions].concat();
+        let simulation =
+            simulate_with_lookup_tables(client, &instructions, payer, &signers, lookup_tables)
+                .await?;
+        return Ok(SendOutcome::Simulated(simulation));
+    }
+    let instructions = prepare_instructions(
+        client,
+        instructions,
+        payer,
+        &signers,
+        lookup_tables,
+        nonce,
+        options,
+    )
+    .await?;
+    for _ in 0..MAX_SEND_ATTEMPTS {
+        let blockhash = RecentBlockhash::fetch_for(client, nonce).await?;
+        let transaction = sign_transaction(
+            &instructions,
+            payer,
+            &signers,
+            blockhash.blockhash,
+            lookup_tables,
+        )?;
+        if options.mode == SendMode::SignOnly {
+            return Ok(SendOutcome::Exported(ExportedTransaction { transaction }));
+        }
+        if let Some(signature) = send_until_expired(client, &transaction, &blockhash).await? {
+            return Ok(SendOutcome::Sent(signature));
+        }
+    }
+    bail!("transaction expired unconfirmed {MAX_SEND_ATTEMPTS} times")
+}
+
+/// A legacy transaction, or a v0 one when there are `lookup_tables` to load accounts from.
+pub fn sign_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    blockhash: Hash,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let message = if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &blockhash,
+        ))
+    } else {
+        VersionedMessage::V0(v0::Message::try_compile(
+            payer,
+            instructions,
+            lookup_tables,
+            blockhash,
+        )?)
+    };
+    //The payer often signs in another role too, which only counts once
+    let mut unique: Vec<&dyn Signer> = Vec::with_capacity(signers.len());
+    for signer in signers {
+        if !unique.iter().any(|other| other.pubkey() == signer.pubkey()) {
+            unique.push(signer);
+        }
+    }
+    Ok(VersionedTransaction::try_new(message, &unique)?)
+}
+
+/// The [`DurableNonce`] of `options`, unless `instructions` write its account themselves.
+pub(crate) fn durable_nonce<'a>(
+    instructions: &[Instruction],
+    options: &'a SendOptions,
+) -> Option<&'a DurableNonce> {
+    let nonce = options.nonce.as_ref()?;
+    let writes_nonce = instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .any(|meta| meta.is_writable && meta.pubkey == nonce.account);
+    (!writes_nonce).then_some(nonce)
+}
+
+/// `signers`, plus the nonce authority when a nonce is used.
+pub(crate) fn nonce_signers<'a>(
+    signers: &[&'a dyn Signer],
+    nonce: Option<&'a DurableNonce>,
+) -> Vec<&'a dyn Signer> {
+    let mut signers = signers.to_vec();
+    if let Some(nonce) = nonce {
+        signers.push(&nonce.authority);
+    }
+    signers
+}
+
+/// `instructions` behind the compute budget instructions of `options`, ready to be signed.
+/// Using a `nonce` puts advancing it in front of everything.
+pub(crate) async fn prepare_instructions(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+    nonce: Option<&DurableNonce>,
+    options: &SendOptions,
+) -> Result<Vec<Instruction>> {
+    let advance: Vec<Instruction> = nonce
+        .map(DurableNonce::advance_instruction)
+        .into_iter()
+        .collect();
+    let instructions = [advance.as_slice(), instructions].concat();
+    let budget = options
+        .compute_budget
+        .instructions_with_lookup_tables(client, &instructions, payer, signers, lookup_tables)
+        .await?;
+    //The cluster only honours a nonce advanced by the very first instruction
+    let (advance, instructions) = instructions.split_at(advance.len());
+    Ok([advance, &budget, instructions].concat())
+}
+
+/// Send `transaction` and wait until it lands or `blockhash` expires, rebroadcasting it
+/// meanwhile. `None` means it expired unconfirmed and can no longer land.
+pub(crate) async fn send_until_expired(
+    client: &RpcClient,
+    transaction: &VersionedTransaction,
+    blockhash: &RecentBlockhash,
+) -> Result<Option<Signature>> {
+    let signature = client.send_transaction(transaction).await?;
+    match wait_until_expired(client, &signature, blockhash, Some(transaction)).await? {
+        Some(Ok(())) => Ok(Some(signature)),
+        Some(Err(err)) => bail!("transaction {signature} failed: {err}"),
+        None => Ok(None),
+    }
+}
+
+/// Poll the status of `signature` until it lands or `blockhash` expires, rebroadcasting
+/// `transaction` in between when it is at hand.
+///
+/// Transaction history is searched as well, so a transaction that landed long ago is still
+/// found. `None` means it never landed and never will.
+///
+/// A nonce only expires once advanced, so a nonce transaction is given up on with an error
+/// after as many blocks as a recent blockhash stays valid for. It may still land until its
+/// nonce is advanced.
+pub(crate) async fn wait_until_expired(
+    client: &RpcClient,
+    signature: &Signature,
+    blockhash: &RecentBlockhash,
+    transaction: Option<&VersionedTransaction>,
+) -> Result<Option<TransactionResult<()>>> {
+    let deadline = match blockhash.nonce_account {
+        Some(_) => Some(client.get_block_height().await? + MAX_PROCESSING_AGE),
+        None => None,
+    };
+    loop {
+        //Checked before the status, so a transaction landing in between is not taken for lost
+        let expired = blockhash.is_expired(client).await?;
+        let status = client
+            .get_signature_status_with_commitment_and_history(signature, client.commitment(), true)
+            .await?;
+        match status {
+            Some(result) => return Ok(Some(result)),
+            None if expired => return Ok(None),
+            None => {
+                if let (Some(deadline), Some(account)) = (deadline, blockhash.nonce_account)
+                    && client.get_block_height().await? > deadline
+                {
+                    bail!(
+                        "transaction {signature} did not land within {MAX_PROCESSING_AGE} blocks, \
+                         it may still land until nonce account {account} is advanced"
+                    );
+                }
+                sleep(CONFIRM_POLL_INTERVAL).await;
+                let Some(transaction) = transaction else {
+                    continue;
+                };
+                //Preflight already passed once, a rebroadcast only guards against a dropped packet
+                let config = RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..RpcSendTransactionConfig::default()
+                };
+                client
+                    .send_transaction_with_config(transaction, config)
+                    .await
+                    .ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use spl_associated_token_account_interface::instruction::create_associated_token_account_idempotent;
+use spl_token_2022_interface::{
+    instruction::transfer_checked as transfer_checked_instruction, state::Account,
+};
+
+use crate::{
+    account::get_token_account,
+    program::TokenProgram,
+    transaction::{SendOptions, SendOutcome, send_instructions},
+};
+
+/// Result of a `transfer_checked`, with both token accounts read back afterwards.
+#[derive(Debug)]
+pub struct Transfer {
+    pub signature: Signature,
+    pub source: Account,
+    pub destination: Account,
+}
+
+/// Transfer `amount` base units of `mint` from `source` to `destination`.
+///
+/// `authority` is the source owner or its delegate, and also pays the fee. A delegate's
+/// allowance shrinks by `amount`.
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_checked(
+    client: &RpcClient,
+    authority: &dyn Signer,
+    program: TokenProgram,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<Transfer>> {
+    transfer_checked_with_signers(
+        client,
+        authority,
+        program,
+        source,
+        mint,
+        destination,
+        &authority.pubkey(),
+        &[authority],
+        amount,
+        decimals,
+        options,
+    )
+    .await
+}
+
+/// Like [`transfer_checked`], for a source owner or delegate that is a multisig approved
+/// by `signers`.
+///
+/// `payer` pays the fee and need not be one of the signers.
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_checked_with_signers(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&dyn Signer],
+    amount: u64,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<Transfer>> {
+    let instruction = transfer_instruction(
+        program,
+        source,
+        mint,
+        destination,
+        authority,
+        signers,
+        amount,
+        decimals,
+    )?;
+    send_transfer(
+        client,
+        payer,
+        &[instruction],
+        source,
+        destination,
+        signers,
+        options,
+    )
+    .await
+}
+
+/// Like [`transfer_checked_with_signers`], into the ATA of the `recipient` wallet, which is
+/// created in the same transaction when missing.
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_checked_to_owner(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    program: TokenProgram,
+    source: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&dyn Signer],
+    amount: u64,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<SendOutcome<Transfer>> {
+    let destination = program.ata_address(recipient, mint);
+    let instructions = [
+        create_associated_token_account_idempotent(&payer.pubkey(), recipient, mint, &program.id()),
+        transfer_instruction(
+            program,
+            source,
+            mint,
+            &destination,
+            authority,
+            signers,
+            amount,
+            decimals,
+        )?,
+    ];
+    send_transfer(
+        client,
+        payer,
+        &instructions,
+        source,
+        &destination,
+        signers,
+        options,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transfer_instruction(
+    program: TokenProgram,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&dyn Signer],
+    amount: u64,
+    decimals: u8,
+) -> Result<Instruction> {
+    let signer_pubkeys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+    Ok(transfer_checked_instruction(
+        &program.id(),
+        source,
+        mint,
+        destination,
+        authority,
+        &signer_pubkeys.iter().collect::<Vec<_>>(),
+        amount,
+        decimals,
+    )?)
+}
+
+/// Send a transfer and read both token accounts back.
+async fn send_transfer(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    instructions: &[Instruction],
+    source: &Pubkey,
+    destination: &Pubkey,
+    signers: &[&dyn Signer],
+    options: &SendOptions,
+) -> Result<SendOutcome<Transfer>> {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    send_instructions(client, instructions, &payer.pubkey(), &all_signers, options)
+        .await?
+        .and_then(|signature| async move {
+            Ok(Transfer {
+                signature,
+                source: get_token_account(client, source).await?,
+                destination: get_token_account(client, destination).await?,
+            })
+        })
+        .await
+}
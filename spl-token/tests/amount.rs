@@ -0,0 +1,69 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup};
+use spl_token_tools::{
+    SendOptions, TokenProgram, amount_to_ui_amount, create_mint, parse_ui_amount,
+    ui_amount_to_amount,
+};
+
+#[test]
+fn ui_amounts_convert_to_base_units() {
+    assert_eq!(ui_amount_to_amount("1", 9).unwrap(), 1_000_000_000);
+    assert_eq!(ui_amount_to_amount("1.5", 6).unwrap(), 1_500_000);
+    assert_eq!(ui_amount_to_amount("0.000001", 6).unwrap(), 1);
+    assert_eq!(ui_amount_to_amount(".25", 2).unwrap(), 25);
+    assert_eq!(ui_amount_to_amount("42", 0).unwrap(), 42);
+    assert_eq!(
+        ui_amount_to_amount("18446744073709551615", 0).unwrap(),
+        u64::MAX
+    );
+}
+
+#[test]
+fn ui_amounts_reject_bad_input() {
+    assert!(ui_amount_to_amount("", 6).is_err());
+    assert!(ui_amount_to_amount(".", 6).is_err());
+    assert!(ui_amount_to_amount("-1", 6).is_err());
+    assert!(ui_amount_to_amount("1e6", 6).is_err());
+    assert!(ui_amount_to_amount("1.0000001", 6).is_err());
+    assert!(ui_amount_to_amount("18446744073709551616", 0).is_err());
+    assert!(ui_amount_to_amount("18446744073.709551616", 9).is_err());
+    assert!(ui_amount_to_amount("1", 20).is_err());
+}
+
+#[test]
+fn base_units_format_without_trailing_zeros() {
+    assert_eq!(amount_to_ui_amount(1_500_000, 6), "1.5");
+    assert_eq!(amount_to_ui_amount(1, 6), "0.000001");
+    assert_eq!(amount_to_ui_amount(2_000_000_000, 9), "2");
+    assert_eq!(amount_to_ui_amount(0, 9), "0");
+    assert_eq!(amount_to_ui_amount(42, 0), "42");
+    assert_eq!(amount_to_ui_amount(u64::MAX, 19), "1.8446744073709551615");
+}
+
+#[tokio::test]
+async fn ui_amounts_use_the_on_chain_decimals() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(
+        &client,
+        &payer,
+        TokenProgram::Token,
+        2,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(
+        parse_ui_amount(&client, &mint.address, "12.5")
+            .await
+            .unwrap(),
+        1_250
+    );
+    assert!(
+        parse_ui_amount(&client, &mint.address, "0.001")
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022_interface::instruction::AuthorityType;
+use spl_token_tools::{
+    SendOptions, TokenProgram, create_ata, create_mint, get_or_create_ata, mint_to, set_authority,
+};
+
+#[tokio::test]
+async fn get_or_create_ata_creates_only_once() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let owner = Keypair::new().pubkey();
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+
+        let created = get_or_create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert!(created.created());
+        assert_eq!(created.address, program.ata_address(&owner, &mint.address));
+        assert_eq!(created.state.owner, owner);
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &created.address,
+            7,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let balance = client.get_balance(&payer.pubkey()).await.unwrap();
+        let existing = get_or_create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert!(!existing.created());
+        assert_eq!(existing.address, created.address);
+        assert_eq!(existing.state.amount, 7);
+        assert_eq!(client.get_balance(&payer.pubkey()).await.unwrap(), balance);
+    }
+}
+
+#[tokio::test]
+async fn create_ata_succeeds_when_it_already_exists() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let owner = Keypair::new().pubkey();
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let first = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &first.address,
+            3,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let second = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(second.address, first.address);
+        assert_eq!(second.state.amount, 3);
+    }
+}
+
+#[tokio::test]
+async fn get_or_create_ata_rejects_a_reassigned_ata() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    set_authority(
+        &client,
+        &payer,
+        program,
+        &ata.address,
+        AuthorityType::AccountOwner,
+        Some(&Keypair::new().pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = get_or_create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
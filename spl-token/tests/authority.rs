@@ -0,0 +1,288 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::{
+    program_option::COption,
+    signature::{Keypair, Signer},
+};
+use spl_token_2022_interface::instruction::AuthorityType;
+use spl_token_tools::{
+    MintExtensions, SendOptions, TokenProgram, create_ata, create_mint,
+    create_mint_with_extensions, create_token_account, get_authority, get_mint, get_token_account,
+    mint_to, set_authority,
+};
+
+#[tokio::test]
+async fn rotate_and_revoke_mint_authorities() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let next = funded_keypair(&svm, 1);
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+
+        let change = set_authority(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            AuthorityType::FreezeAccount,
+            Some(&next.pubkey()),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(change.previous, Some(payer.pubkey()));
+        assert_eq!(change.current, Some(next.pubkey()));
+
+        let change = set_authority(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            AuthorityType::MintTokens,
+            None,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(change.current, None);
+
+        let state = get_mint(&client, &mint.address).await.unwrap();
+        assert_eq!(state.mint_authority, COption::None);
+        assert_eq!(state.freeze_authority, COption::Some(next.pubkey()));
+    }
+}
+
+#[tokio::test]
+async fn revoked_mint_authority_fixes_the_supply() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        1_000,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    set_authority(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        AuthorityType::MintTokens,
+        None,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert!(
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            1,
+            &SendOptions::default()
+        )
+        .await
+        .is_err()
+    );
+    let revoke_again = set_authority(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        AuthorityType::MintTokens,
+        None,
+        &SendOptions::default(),
+    )
+    .await;
+    assert!(revoke_again.is_err());
+    assert_eq!(
+        get_mint(&client, &mint.address).await.unwrap().supply,
+        1_000
+    );
+}
+
+#[tokio::test]
+async fn set_authority_checks_the_current_authority_first() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let impostor = funded_keypair(&svm, 1);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+
+    let result = set_authority(
+        &client,
+        &impostor,
+        program,
+        &mint.address,
+        AuthorityType::MintTokens,
+        Some(&impostor.pubkey()),
+        &SendOptions::default(),
+    )
+    .await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains(&payer.pubkey().to_string()), "{error}");
+}
+
+#[tokio::test]
+async fn account_owner_and_close_authority() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let new_owner = funded_keypair(&svm, 1);
+    let closer = Keypair::new();
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let account = create_token_account(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        get_authority(&client, &account.address, &AuthorityType::CloseAccount)
+            .await
+            .unwrap(),
+        Some(payer.pubkey())
+    );
+
+    let change = set_authority(
+        &client,
+        &payer,
+        program,
+        &account.address,
+        AuthorityType::CloseAccount,
+        Some(&closer.pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(change.current, Some(closer.pubkey()));
+
+    assert!(
+        set_authority(
+            &client,
+            &payer,
+            program,
+            &account.address,
+            AuthorityType::AccountOwner,
+            None,
+            &SendOptions::default()
+        )
+        .await
+        .is_err()
+    );
+    set_authority(
+        &client,
+        &payer,
+        program,
+        &account.address,
+        AuthorityType::AccountOwner,
+        Some(&new_owner.pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let state = get_token_account(&client, &account.address).await.unwrap();
+    assert_eq!(state.owner, new_owner.pubkey());
+    assert_eq!(state.close_authority, COption::Some(closer.pubkey()));
+}
+
+#[tokio::test]
+async fn mint_close_authority() {
+    let program = TokenProgram::Token2022;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let closer = funded_keypair(&svm, 1);
+    let mint = Keypair::new();
+    let extensions = MintExtensions {
+        close_authority: Some(payer.pubkey()),
+        ..MintExtensions::default()
+    };
+    create_mint_with_extensions(
+        &client,
+        &payer,
+        &mint,
+        program,
+        0,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        get_authority(&client, &mint.pubkey(), &AuthorityType::CloseAccount)
+            .await
+            .unwrap(),
+        Some(payer.pubkey())
+    );
+
+    let rotated = set_authority(
+        &client,
+        &payer,
+        program,
+        &mint.pubkey(),
+        AuthorityType::CloseAccount,
+        Some(&closer.pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let revoked = set_authority(
+        &client,
+        &closer,
+        program,
+        &mint.pubkey(),
+        AuthorityType::CloseAccount,
+        None,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(rotated.current, Some(closer.pubkey()));
+    assert_eq!(revoked.previous, Some(closer.pubkey()));
+    assert_eq!(revoked.current, None);
+    //Without the extension a mint has no close authority to speak of
+    let plain = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let error = get_authority(&client, &plain.address, &AuthorityType::CloseAccount)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("MintCloseAuthority"), "{error}");
+}
@@ -0,0 +1,243 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::signature::Signer;
+use spl_token_tools::{
+    SendOptions, TokenProgram, approve, burn, burn_checked, create_ata, create_mint, get_mint,
+    get_token_account, mint_to,
+};
+
+#[tokio::test]
+async fn burn_reduces_supply_and_balance() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 6, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            5_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let burned = burn(
+            &client,
+            &payer,
+            program,
+            &ata.address,
+            &mint.address,
+            1_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(burned.mint.supply, 4_000_000);
+        assert_eq!(burned.account.amount, 4_000_000);
+
+        let burned = burn_checked(
+            &client,
+            &payer,
+            program,
+            &ata.address,
+            &mint.address,
+            500_000,
+            6,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(burned.mint.supply, 3_500_000);
+        assert_eq!(burned.account.amount, 3_500_000);
+    }
+}
+
+#[tokio::test]
+async fn burn_checked_rejects_wrong_decimals() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 6, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            1_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let result = burn_checked(
+            &client,
+            &payer,
+            program,
+            &ata.address,
+            &mint.address,
+            1_000,
+            9,
+            &SendOptions::default(),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            get_mint(&client, &mint.address).await.unwrap().supply,
+            1_000
+        );
+    }
+}
+
+#[tokio::test]
+async fn delegate_burns_within_its_allowance() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let delegate = funded_keypair(&svm, 1);
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            100,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        approve(
+            &client,
+            &payer,
+            program,
+            &ata.address,
+            &delegate.pubkey(),
+            30,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let burned = burn_checked(
+            &client,
+            &delegate,
+            program,
+            &ata.address,
+            &mint.address,
+            20,
+            0,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(burned.mint.supply, 80);
+        assert_eq!(burned.account.amount, 80);
+        assert_eq!(burned.account.delegated_amount, 10);
+
+        let over_allowance = burn(
+            &client,
+            &delegate,
+            program,
+            &ata.address,
+            &mint.address,
+            20,
+            &SendOptions::default(),
+        )
+        .await;
+        assert!(over_allowance.is_err());
+    }
+}
+
+#[tokio::test]
+async fn burn_requires_owner_or_delegate() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let stranger = funded_keypair(&svm, 1);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        100,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = burn(
+        &client,
+        &stranger,
+        program,
+        &ata.address,
+        &mint.address,
+        1,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .amount,
+        100
+    );
+}
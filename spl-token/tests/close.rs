@@ -0,0 +1,230 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022_interface::instruction::AuthorityType;
+use spl_token_tools::{
+    SendOptions, TokenProgram, close_account, create_ata, create_mint, create_token_account,
+    freeze_account, get_token_accounts_by_owner, mint_to, set_authority, sweep_empty_accounts,
+};
+
+#[tokio::test]
+async fn close_account_returns_the_rent() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let destination = Keypair::new().pubkey();
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let account = create_token_account(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let rent = client.get_balance(&account.address).await.unwrap();
+
+        let closed = close_account(
+            &client,
+            &payer,
+            program,
+            &account.address,
+            &destination,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(closed.lamports, rent);
+        assert_eq!(client.get_balance(&destination).await.unwrap(), rent);
+        assert!(
+            client
+                .get_account_with_commitment(&account.address, client.commitment())
+                .await
+                .unwrap()
+                .value
+                .is_none()
+        );
+    }
+}
+
+#[tokio::test]
+async fn close_account_refuses_a_non_empty_account() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        1,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = close_account(
+        &client,
+        &payer,
+        program,
+        &ata.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn sweep_closes_only_what_the_owner_can_close() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 100);
+    let destination = Keypair::new().pubkey();
+    let mut expected = Vec::new();
+    for program in PROGRAMS {
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        expected.push(
+            create_ata(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &payer.pubkey(),
+                &SendOptions::default(),
+            )
+            .await
+            .unwrap_sent()
+            .address,
+        );
+        for _ in 0..12 {
+            let account = create_token_account(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &payer.pubkey(),
+                &SendOptions::default(),
+            )
+            .await
+            .unwrap_sent();
+            expected.push(account.address);
+        }
+    }
+
+    let program = TokenProgram::Token;
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let holding = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &holding.address,
+        5,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let frozen = create_token_account(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    freeze_account(
+        &client,
+        &payer,
+        program,
+        &frozen.address,
+        &mint.address,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let delegated_close = create_token_account(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    set_authority(
+        &client,
+        &payer,
+        program,
+        &delegated_close.address,
+        AuthorityType::CloseAccount,
+        Some(&Keypair::new().pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let sweep = sweep_empty_accounts(&client, &payer, &destination, &SendOptions::default())
+        .await
+        .unwrap_sent();
+
+    let mut closed: Vec<_> = sweep.closed.iter().map(|closed| closed.address).collect();
+    closed.sort();
+    expected.sort();
+    assert_eq!(closed, expected);
+    let mut skipped = sweep.skipped.clone();
+    skipped.sort();
+    let mut expected_skipped = vec![frozen.address, delegated_close.address];
+    expected_skipped.sort();
+    assert_eq!(skipped, expected_skipped);
+    assert_eq!(
+        client.get_balance(&destination).await.unwrap(),
+        sweep.lamports()
+    );
+
+    let remaining: Vec<_> = get_token_accounts_by_owner(&client, &payer.pubkey())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|account| account.address)
+        .collect();
+    assert_eq!(remaining.len(), 3);
+    assert!(remaining.contains(&holding.address));
+}
@@ -0,0 +1,462 @@
+//! In-process cluster for the integration tests.
+//!
+//! [`LiteSvmSender`] answers the JSON-RPC calls the library makes from a [`LiteSVM`] bank,
+//! so the real async functions run end to end against an `RpcClient` without any network.
+
+#![allow(dead_code)]
+
+use agave_feature_set::{FeatureSet, stricter_abi_and_runtime_constraints};
+use async_trait::async_trait;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use litesvm::LiteSVM;
+use serde_json::{Value, json};
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_filter::RpcFilterType,
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::{
+    account::{Account, ReadableAccount},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    slot_hashes::SlotHashes,
+    transaction::VersionedTransaction,
+};
+use spl_token_2022_interface::{
+    extension::StateWithExtensions,
+    native_mint::DECIMALS as NATIVE_DECIMALS,
+    state::{Account as TokenAccount, Mint},
+};
+use spl_token_tools::{SendOutcome, TokenProgram};
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+const TOKEN_PROGRAMS: [Pubkey; 2] = [spl_token_interface::ID, spl_token_2022_interface::ID];
+
+/// Blocks a blockhash stays valid for, matching mainnet.
+pub const MAX_PROCESSING_AGE: u64 = 150;
+
+/// Largest serialized transaction a node accepts.
+const PACKET_DATA_SIZE: usize = 1232;
+
+pub struct LiteSvmSender {
+    svm: Arc<Mutex<LiteSVM>>,
+    controls: Controls,
+    /// Slot each transaction landed in, the bank keeps no such record.
+    landed: Mutex<HashMap<Signature, u64>>,
+}
+
+/// Knobs for cluster behaviour the bank has no notion of.
+#[derive(Clone, Default)]
+pub struct Controls {
+    /// Upcoming transactions to lose on the way to the leader. A lost transaction is
+    /// acknowledged, then the bank moves on until its blockhash has expired.
+    pub drops: Arc<AtomicUsize>,
+    /// Answer to `getRecentPrioritizationFees`, a quiet cluster by default.
+    pub prioritization_fees: Arc<Mutex<Vec<u64>>>,
+    /// Every transaction sent, lost or not, in order.
+    pub sent: Arc<Mutex<Vec<VersionedTransaction>>>,
+}
+
+/// Both token programs, for tests that run against each of them.
+pub const PROGRAMS: [TokenProgram; 2] = [TokenProgram::Token, TokenProgram::Token2022];
+
+/// What an operation produced, for tests expecting its transaction to be sent.
+pub trait Sent<T> {
+    fn unwrap_sent(self) -> T;
+}
+
+impl<T> Sent<T> for anyhow::Result<SendOutcome<T>> {
+    fn unwrap_sent(self) -> T {
+        self.unwrap().sent().unwrap()
+    }
+}
+
+/// An `RpcClient` backed by a fresh bank, plus the bank for direct inspection.
+pub fn setup() -> (RpcClient, Arc<Mutex<LiteSVM>>) {
+    let (client, svm, _) = setup_with_controls();
+    (client, svm)
+}
+
+/// Like [`setup`], plus the [`Controls`] of the cluster.
+pub fn setup_with_controls() -> (RpcClient, Arc<Mutex<LiteSVM>>, Controls) {
+    let svm = Arc::new(Mutex::new(new_bank()));
+    let controls = Controls::default();
+    let client = RpcClient::new_sender(
+        LiteSvmSender {
+            svm: svm.clone(),
+            controls: controls.clone(),
+            landed: Mutex::default(),
+        },
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    );
+    (client, svm, controls)
+}
+
+fn decode_transaction(params: &Value) -> ClientResult<VersionedTransaction> {
+    let bytes = BASE64_STANDARD
+        .decode(params[0].as_str().unwrap_or_default())
+        .map_err(|err| RpcError::ParseError(err.to_string()))?;
+    //Like a node, refuse what would not fit a packet
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(RpcError::RpcRequestError(format!(
+            "transaction too large: {} bytes (max: {PACKET_DATA_SIZE} bytes)",
+            bytes.len()
+        ))
+        .into());
+    }
+    Ok(bincode::deserialize(&bytes).map_err(|err| RpcError::ParseError(err.to_string()))?)
+}
+
+/// A bank with the features active on mainnet rather than every known one: the bundled
+/// Token-2022 build cannot reallocate accounts under the stricter account data ABI.
+///
+/// The wrapped SOL mints are added since every real cluster has them.
+fn new_bank() -> LiteSVM {
+    let mut features = FeatureSet::all_enabled();
+    features.deactivate(&stricter_abi_and_runtime_constraints::id());
+    let mut svm = LiteSVM::default()
+        .with_feature_set(features)
+        .with_builtins()
+        .with_lamports(1_000_000 * 1_000_000_000)
+        .with_sysvars()
+        .with_precompiles()
+        .with_default_programs()
+        .with_sigverify(true)
+        .with_blockhash_check(true);
+
+    for program in [TokenProgram::Token, TokenProgram::Token2022] {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            decimals: NATIVE_DECIMALS,
+            is_initialized: true,
+            ..Mint::default()
+        }
+        .pack_into_slice(&mut data);
+        let account = Account {
+            lamports: svm.minimum_balance_for_rent_exemption(Mint::LEN),
+            data,
+            owner: program.id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        svm.set_account(program.native_mint(), account).unwrap();
+    }
+    svm
+}
+
+/// A new keypair holding `sol` SOL.
+pub fn funded_keypair(svm: &Arc<Mutex<LiteSVM>>, sol: u64) -> Keypair {
+    let keypair = Keypair::new();
+    let mut svm = svm.lock().unwrap();
+    svm.airdrop(&keypair.pubkey(), sol * 1_000_000_000).unwrap();
+    advance_block(&mut svm);
+    keypair
+}
+
+/// Move to the next slot with a new blockhash, like a cluster producing a block.
+pub fn advance_block(svm: &mut LiteSVM) {
+    let slot = slot(svm);
+    //Lookup tables are derived from slots the bank still has the hash of
+    let mut slot_hashes = svm.get_sysvar::<SlotHashes>();
+    slot_hashes.add(slot, svm.latest_blockhash());
+    svm.set_sysvar(&slot_hashes);
+    svm.warp_to_slot(slot + 1);
+    svm.expire_blockhash();
+}
+
+fn slot(svm: &LiteSVM) -> u64 {
+    svm.get_sysvar::<solana_sdk::clock::Clock>().slot
+}
+
+fn with_context(svm: &LiteSVM, value: Value) -> Value {
+    json!({ "context": { "slot": slot(svm) }, "value": value })
+}
+
+fn pubkey_param(params: &Value, index: usize) -> ClientResult<Pubkey> {
+    params[index]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| RpcError::ParseError(format!("expected a pubkey at param {index}")).into())
+}
+
+fn encode_account(account: &impl ReadableAccount) -> Value {
+    json!({
+        "lamports": account.lamports(),
+        "data": [BASE64_STANDARD.encode(account.data()), "base64"],
+        "owner": account.owner().to_string(),
+        "executable": account.executable(),
+        "rentEpoch": account.rent_epoch(),
+        "space": account.data().len(),
+    })
+}
+
+fn matches(filter: &RpcFilterType, data: &[u8]) -> bool {
+    match filter {
+        RpcFilterType::DataSize(len) => data.len() as u64 == *len,
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        RpcFilterType::TokenAccountState => true,
+    }
+}
+
+fn unsupported(request: RpcRequest) -> ClientResult<Value> {
+    Err(RpcError::ForUser(format!("{request} is not supported by LiteSvmSender")).into())
+}
+
+#[async_trait]
+impl RpcSender for LiteSvmSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut svm = self.svm.lock().unwrap();
+        match request {
+            RpcRequest::GetLatestBlockhash => Ok(with_context(
+                &svm,
+                json!({
+                    "blockhash": svm.latest_blockhash().to_string(),
+                    "lastValidBlockHeight": slot(&svm) + MAX_PROCESSING_AGE,
+                }),
+            )),
+            RpcRequest::IsBlockhashValid => {
+                let valid = params[0].as_str() == Some(&svm.latest_blockhash().to_string());
+                Ok(with_context(&svm, json!(valid)))
+            }
+            //Finalized lags a block behind, so it is always one the bank has the hash of
+            RpcRequest::GetSlot if params[0]["commitment"] == "finalized" => {
+                Ok(json!(slot(&svm).saturating_sub(1)))
+            }
+            RpcRequest::GetSlot | RpcRequest::GetBlockHeight => Ok(json!(slot(&svm))),
+            RpcRequest::GetBalance => {
+                let pubkey = pubkey_param(&params, 0)?;
+                Ok(with_context(
+                    &svm,
+                    json!(svm.get_balance(&pubkey).unwrap_or(0)),
+                ))
+            }
+            RpcRequest::GetAccountInfo => {
+                let pubkey = pubkey_param(&params, 0)?;
+                let account = svm
+                    .get_account(&pubkey)
+                    .map(|account| encode_account(&account));
+                Ok(with_context(&svm, account.unwrap_or(Value::Null)))
+            }
+            RpcRequest::GetMultipleAccounts => {
+                let accounts = params[0]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|pubkey| {
+                        pubkey
+                            .as_str()
+                            .and_then(|s| s.parse().ok())
+                            .and_then(|pubkey| svm.get_account(&pubkey))
+                            .map(|account| encode_account(&account))
+                            .unwrap_or(Value::Null)
+                    })
+                    .collect::<Vec<_>>();
+                Ok(with_context(&svm, json!(accounts)))
+            }
+            RpcRequest::GetProgramAccounts => {
+                let program = pubkey_param(&params, 0)?;
+                let filters: Vec<RpcFilterType> =
+                    serde_json::from_value(params[1]["filters"].clone()).unwrap_or_default();
+                let accounts = svm
+                    .accounts_db()
+                    .inner
+                    .iter()
+                    .filter(|(_, account)| account.owner() == &program)
+                    .filter(|(_, account)| filters.iter().all(|filter| matches(filter, account.data())))
+                    .map(|(pubkey, account)| {
+                        json!({ "pubkey": pubkey.to_string(), "account": encode_account(account) })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!(accounts))
+            }
+            RpcRequest::GetTokenAccountsByOwner => {
+                let owner = pubkey_param(&params, 0)?;
+                let mint = params[1]["mint"].as_str().and_then(|s| s.parse().ok());
+                let program = params[1]["programId"].as_str().and_then(|s| s.parse().ok());
+                let accounts = svm
+                    .accounts_db()
+                    .inner
+                    .iter()
+                    .filter_map(|(pubkey, account)| {
+                        let state = StateWithExtensions::<TokenAccount>::unpack(account.data());
+                        let state = state.ok()?.base;
+                        let owned_by_token_program = match program {
+                            Some(program) => account.owner() == &program,
+                            None => TOKEN_PROGRAMS.contains(account.owner()),
+                        };
+                        (owned_by_token_program
+                            && state.owner == owner
+                            && mint.is_none_or(|mint| state.mint == mint))
+                        .then(|| json!({ "pubkey": pubkey.to_string(), "account": encode_account(account) }))
+                    })
+                    .collect::<Vec<_>>();
+                Ok(with_context(&svm, json!(accounts)))
+            }
+            RpcRequest::GetMinimumBalanceForRentExemption => {
+                let len = params[0].as_u64().unwrap_or_default() as usize;
+                Ok(json!(svm.minimum_balance_for_rent_exemption(len)))
+            }
+            RpcRequest::RequestAirdrop => {
+                let pubkey = pubkey_param(&params, 0)?;
+                let lamports = params[1].as_u64().unwrap_or_default();
+                let meta = svm.airdrop(&pubkey, lamports).map_err(|failed| {
+                    RpcError::ForUser(format!("airdrop failed: {}", failed.err))
+                })?;
+                self.landed
+                    .lock()
+                    .unwrap()
+                    .insert(meta.signature, slot(&svm));
+                advance_block(&mut svm);
+                Ok(json!(meta.signature.to_string()))
+            }
+            RpcRequest::SendTransaction => {
+                let transaction = decode_transaction(&params)?;
+                self.controls.sent.lock().unwrap().push(transaction.clone());
+                let dropped = self
+                    .controls
+                    .drops
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |drops| {
+                        drops.checked_sub(1)
+                    })
+                    .is_ok();
+                if dropped {
+                    let slot = slot(&svm);
+                    svm.warp_to_slot(slot + MAX_PROCESSING_AGE + 1);
+                    svm.expire_blockhash();
+                    return Ok(json!(transaction.signatures[0].to_string()));
+                }
+                match svm.send_transaction(transaction) {
+                    Ok(meta) => {
+                        self.landed
+                            .lock()
+                            .unwrap()
+                            .insert(meta.signature, slot(&svm));
+                        advance_block(&mut svm);
+                        Ok(json!(meta.signature.to_string()))
+                    }
+                    Err(failed) => {
+                        let data = serde_json::from_value(json!({
+                            "err": failed.err,
+                            "logs": failed.meta.logs,
+                            "unitsConsumed": failed.meta.compute_units_consumed,
+                        }))?;
+                        Err(RpcError::RpcResponseError {
+                            code: -32002,
+                            message: format!("Transaction simulation failed: {}", failed.err),
+                            data: RpcResponseErrorData::SendTransactionPreflightFailure(data),
+                        }
+                        .into())
+                    }
+                }
+            }
+            RpcRequest::SimulateTransaction => {
+                let transaction = decode_transaction(&params)?;
+                //Like a node, signatures are only checked when asked to, so a partially signed
+                //transaction can be simulated
+                let bank = mem::take(&mut *svm).with_sigverify(params[1]["sigVerify"] == true);
+                let simulated = bank.simulate_transaction(transaction);
+                *svm = bank.with_sigverify(true);
+                let result = match simulated {
+                    Ok(simulated) => {
+                        //Requested accounts as the transaction leaves them, null once closed
+                        let accounts =
+                            params[1]["accounts"]["addresses"]
+                                .as_array()
+                                .map(|addresses| {
+                                    addresses
+                                        .iter()
+                                        .map(|address| {
+                                            let address =
+                                                address.as_str()?.parse::<Pubkey>().ok()?;
+                                            let account = simulated
+                                                .post_accounts
+                                                .iter()
+                                                .find(|(key, _)| *key == address)
+                                                .map(|(_, account)| account.clone().into())
+                                                .or_else(|| svm.get_account(&address))?;
+                                            (account.lamports > 0).then(|| encode_account(&account))
+                                        })
+                                        .collect::<Vec<_>>()
+                                });
+                        json!({
+                            "err": null,
+                            "logs": simulated.meta.logs,
+                            "accounts": accounts,
+                            "unitsConsumed": simulated.meta.compute_units_consumed,
+                        })
+                    }
+                    Err(failed) => json!({
+                        "err": failed.err,
+                        "logs": failed.meta.logs,
+                        "unitsConsumed": failed.meta.compute_units_consumed,
+                    }),
+                };
+                Ok(with_context(&svm, result))
+            }
+            RpcRequest::GetRecentPrioritizationFees => {
+                let slot = slot(&svm);
+                let fees = self.controls.prioritization_fees.lock().unwrap();
+                let fees = fees
+                    .iter()
+                    .enumerate()
+                    .map(|(age, fee)| {
+                        json!({ "slot": slot.saturating_sub(age as u64), "prioritizationFee": fee })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!(fees))
+            }
+            RpcRequest::GetSignatureStatuses => {
+                //Like a real cluster, only recent statuses are kept unless history is searched
+                let history = params[1]["searchTransactionHistory"] == true;
+                let landed = self.landed.lock().unwrap();
+                let statuses = params[0]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|signature| {
+                        let signature = signature.as_str()?.parse::<Signature>().ok()?;
+                        let result = svm.get_transaction(&signature)?;
+                        let landed_slot = landed.get(&signature).copied().unwrap_or(slot(&svm));
+                        if !history && landed_slot + MAX_PROCESSING_AGE < slot(&svm) {
+                            return None;
+                        }
+                        let err = result.as_ref().err().map(|failed| failed.err.clone());
+                        let status = match &err {
+                            Some(err) => json!({ "Err": err }),
+                            None => json!({ "Ok": null }),
+                        };
+                        Some(json!({
+                            "slot": landed_slot,
+                            "confirmations": null,
+                            "status": status,
+                            "err": err,
+                            "confirmationStatus": "finalized",
+                        }))
+                    })
+                    .collect::<Vec<_>>();
+                Ok(with_context(&svm, json!(statuses)))
+            }
+            request => unsupported(request),
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "litesvm".to_string()
+    }
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup, setup_with_controls};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use spl_token_tools::{ComputeBudget, PriorityFee, SendOptions, transaction::send_instructions};
+
+fn unit_limit(instruction: &Instruction) -> u32 {
+    assert_eq!(
+        instruction.program_id,
+        solana_compute_budget_interface::id()
+    );
+    assert_eq!(instruction.data[0], 2);
+    u32::from_le_bytes(instruction.data[1..5].try_into().unwrap())
+}
+
+#[test]
+fn priority_fee_policies_parse() {
+    assert_eq!("none".parse::<PriorityFee>().unwrap(), PriorityFee::None);
+    assert_eq!(
+        "auto".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Recent { percentile: 50 }
+    );
+    assert_eq!(
+        "auto:90".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Recent { percentile: 90 }
+    );
+    assert_eq!(
+        "5000".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Fixed(5_000)
+    );
+    assert!("auto:101".parse::<PriorityFee>().is_err());
+    assert!("fast".parse::<PriorityFee>().is_err());
+}
+
+#[tokio::test]
+async fn unit_limit_is_the_simulated_consumption_plus_margin() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 1);
+    let transfer = [transfer(
+        &payer.pubkey(),
+        &Keypair::new().pubkey(),
+        1_000_000,
+    )];
+    let budget = ComputeBudget {
+        unit_margin: Some(20),
+        priority_fee: PriorityFee::Fixed(1_000),
+        max_unit_price: None,
+    };
+
+    let instructions = budget
+        .instructions(&client, &transfer, &payer.pubkey(), &[&payer])
+        .await
+        .unwrap();
+
+    assert_eq!(instructions.len(), 2);
+    let limit = unit_limit(&instructions[0]);
+    assert!((150..5_000).contains(&limit), "{limit}");
+    assert_eq!(
+        instructions[1],
+        ComputeBudgetInstruction::set_compute_unit_price(1_000)
+    );
+
+    let wider = ComputeBudget {
+        unit_margin: Some(100),
+        ..budget
+    }
+    .instructions(&client, &transfer, &payer.pubkey(), &[&payer])
+    .await
+    .unwrap();
+    assert!(unit_limit(&wider[0]) > limit);
+}
+
+#[tokio::test]
+async fn recent_fees_set_the_unit_price() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 1);
+    let transfer = [transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1)];
+    let budget = |percentile, max_unit_price| ComputeBudget {
+        unit_margin: None,
+        priority_fee: PriorityFee::Recent { percentile },
+        max_unit_price,
+    };
+
+    let quiet = budget(50, None)
+        .instructions(&client, &transfer, &payer.pubkey(), &[&payer])
+        .await
+        .unwrap();
+    assert!(quiet.is_empty());
+
+    *controls.prioritization_fees.lock().unwrap() = vec![300, 0, 10_000, 100, 200];
+    let price = |budget: ComputeBudget| {
+        let client = &client;
+        let transfer = &transfer;
+        async move { budget.unit_price(client, transfer).await.unwrap() }
+    };
+    assert_eq!(price(budget(50, None)).await, 200);
+    assert_eq!(price(budget(100, None)).await, 10_000);
+    assert_eq!(price(budget(0, None)).await, 0);
+    assert_eq!(price(budget(100, Some(1_000))).await, 1_000);
+}
+
+#[tokio::test]
+async fn failing_instructions_are_caught_by_the_simulation() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 1);
+    let overdraft = [transfer(
+        &payer.pubkey(),
+        &Keypair::new().pubkey(),
+        5_000_000_000,
+    )];
+
+    let err = ComputeBudget::DEFAULT
+        .instructions(&client, &overdraft, &payer.pubkey(), &[&payer])
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("simulation failed"), "{err}");
+}
+
+#[tokio::test]
+async fn sent_transactions_carry_their_budget() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 1);
+    let options = SendOptions {
+        compute_budget: ComputeBudget {
+            priority_fee: PriorityFee::Fixed(25_000),
+            ..ComputeBudget::DEFAULT
+        },
+        ..SendOptions::default()
+    };
+
+    send_instructions(
+        &client,
+        &[transfer(
+            &payer.pubkey(),
+            &Keypair::new().pubkey(),
+            1_000_000,
+        )],
+        &payer.pubkey(),
+        &[&payer],
+        &options,
+    )
+    .await
+    .unwrap_sent();
+
+    let sent = controls.sent.lock().unwrap();
+    let message = &sent.last().unwrap().message;
+    let keys = message.static_account_keys();
+    let instructions: Vec<Instruction> = message
+        .instructions()
+        .iter()
+        .map(|compiled| Instruction {
+            program_id: keys[compiled.program_id_index as usize],
+            accounts: Vec::new(),
+            data: compiled.data.clone(),
+        })
+        .collect();
+    assert_eq!(instructions.len(), 3);
+    assert!(unit_limit(&instructions[0]) > 150);
+    assert_eq!(
+        instructions[1].data,
+        ComputeBudgetInstruction::set_compute_unit_price(25_000).data
+    );
+    assert_eq!(
+        instructions[2].program_id,
+        solana_system_interface::program::ID
+    );
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    program_option::COption,
+    signature::{Keypair, Signer},
+};
+use spl_token_tools::{
+    CreatedAta, CreatedMint, SendOptions, TokenProgram, approve, approve_checked, create_ata,
+    create_mint, mint_to, revoke, transfer_checked,
+};
+
+/// A mint with 2 decimals and an ATA of `owner` holding 100.00 tokens.
+async fn funded_account(
+    client: &RpcClient,
+    owner: &Keypair,
+    program: TokenProgram,
+) -> (CreatedMint, CreatedAta) {
+    let mint = create_mint(client, owner, program, 2, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        client,
+        owner,
+        program,
+        &mint.address,
+        &owner.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        client,
+        owner,
+        program,
+        &mint.address,
+        &ata.address,
+        10_000,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    (mint, ata)
+}
+
+#[tokio::test]
+async fn approve_and_revoke_report_the_delegate() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let owner = funded_keypair(&svm, 10);
+        let delegate = Keypair::new();
+        let (mint, ata) = funded_account(&client, &owner, program).await;
+
+        let approved = approve_checked(
+            &client,
+            &owner,
+            program,
+            &ata.address,
+            &mint.address,
+            &delegate.pubkey(),
+            2_500,
+            2,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(approved.account.delegate, COption::Some(delegate.pubkey()));
+        assert_eq!(approved.account.delegated_amount, 2_500);
+
+        let revoked = revoke(
+            &client,
+            &owner,
+            program,
+            &ata.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(revoked.account.delegate, COption::None);
+        assert_eq!(revoked.account.delegated_amount, 0);
+    }
+}
+
+#[tokio::test]
+async fn approve_checked_rejects_wrong_decimals() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let owner = funded_keypair(&svm, 10);
+    let (mint, ata) = funded_account(&client, &owner, program).await;
+
+    let result = approve_checked(
+        &client,
+        &owner,
+        program,
+        &ata.address,
+        &mint.address,
+        &Keypair::new().pubkey(),
+        100,
+        6,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn delegate_transfers_up_to_its_allowance() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let owner = funded_keypair(&svm, 10);
+        let delegate = funded_keypair(&svm, 1);
+        let recipient = Keypair::new();
+        let (mint, ata) = funded_account(&client, &owner, program).await;
+        let destination = create_ata(
+            &client,
+            &owner,
+            program,
+            &mint.address,
+            &recipient.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        approve(
+            &client,
+            &owner,
+            program,
+            &ata.address,
+            &delegate.pubkey(),
+            3_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let transfer = transfer_checked(
+            &client,
+            &delegate,
+            program,
+            &ata.address,
+            &mint.address,
+            &destination.address,
+            2_000,
+            2,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(transfer.source.amount, 8_000);
+        assert_eq!(transfer.source.delegated_amount, 1_000);
+        assert_eq!(transfer.destination.amount, 2_000);
+
+        let over_allowance = transfer_checked(
+            &client,
+            &delegate,
+            program,
+            &ata.address,
+            &mint.address,
+            &destination.address,
+            2_000,
+            2,
+            &SendOptions::default(),
+        )
+        .await;
+        assert!(over_allowance.is_err());
+
+        revoke(
+            &client,
+            &owner,
+            program,
+            &ata.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let after_revoke = transfer_checked(
+            &client,
+            &delegate,
+            program,
+            &ata.address,
+            &mint.address,
+            &destination.address,
+            500,
+            2,
+            &SendOptions::default(),
+        )
+        .await;
+        assert!(after_revoke.is_err());
+    }
+}
@@ -0,0 +1,497 @@
+mod common;
+
+use common::{
+    MAX_PROCESSING_AGE, PROGRAMS, Sent, advance_block, funded_keypair, setup, setup_with_controls,
+};
+use serde_json::json;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token_tools::{
+    DurableNonce, Recipient, SendOptions, TokenProgram, create_ata, create_mint,
+    create_nonce_account, distribute, get_nonce_account, get_token_account, mint_to,
+    parse_recipients, transfer_checked,
+};
+use std::{fs, path::PathBuf, sync::Arc};
+
+fn state_path() -> PathBuf {
+    std::env::temp_dir().join(format!("distribute-{}.json", Keypair::new().pubkey()))
+}
+
+#[test]
+fn parse_recipients_skips_header_and_comments() {
+    let owner = Keypair::new().pubkey();
+    let csv = format!("recipient,amount\n\n# team\n{owner}, 1.5\n{owner},2\n");
+
+    let recipients = parse_recipients(&csv, 2).unwrap();
+
+    assert_eq!(
+        recipients,
+        [
+            Recipient { owner, amount: 150 },
+            Recipient { owner, amount: 200 }
+        ]
+    );
+    for bad in [
+        format!("{owner}"),
+        format!("{owner},0"),
+        format!("{owner},1.234"),
+        "not-a-pubkey,1".to_string(),
+    ] {
+        assert!(parse_recipients(&bad, 2).is_err(), "{bad}");
+    }
+}
+
+#[tokio::test]
+async fn distribute_packs_transfers_and_creates_missing_atas() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            1_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let recipients: Vec<Recipient> = (0..25)
+            .map(|i| Recipient {
+                owner: Keypair::new().pubkey(),
+                amount: 100 * (i + 1),
+            })
+            .collect();
+        for recipient in &recipients[..5] {
+            create_ata(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &recipient.owner,
+                &SendOptions::default(),
+            )
+            .await
+            .unwrap_sent();
+        }
+        let state = state_path();
+
+        let distribution = distribute(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &recipients,
+            &state,
+            false,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(distribution.sent, 25);
+        assert_eq!(distribution.resumed, 0);
+        assert_eq!(distribution.created_accounts, 20);
+        assert!((2..25).contains(&distribution.signatures.len()));
+        for recipient in &recipients {
+            let ata = program.ata_address(&recipient.owner, &mint.address);
+            let account = get_token_account(&client, &ata).await.unwrap();
+            assert_eq!(account.amount, recipient.amount);
+        }
+
+        let rerun = distribute(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &recipients,
+            &state,
+            false,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(rerun.sent, 0);
+        assert_eq!(rerun.resumed, 25);
+        assert!(rerun.signatures.is_empty());
+        let ata = program.ata_address(&recipients[0].owner, &mint.address);
+        assert_eq!(
+            get_token_account(&client, &ata).await.unwrap().amount,
+            recipients[0].amount
+        );
+        fs::remove_file(state).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn distribute_leaves_room_for_advancing_a_nonce() {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let authority = Arc::new(funded_keypair(&svm, 1));
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        1_000,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    //Existing ATAs, so the batches fill up on size rather than on the instruction trace
+    let recipients: Vec<Recipient> = (0..30)
+        .map(|_| Recipient {
+            owner: Keypair::new().pubkey(),
+            amount: 3,
+        })
+        .collect();
+    for recipient in &recipients {
+        create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &recipient.owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+    }
+    let nonce = create_nonce_account(
+        &client,
+        &payer,
+        &authority.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let options = SendOptions {
+        nonce: Some(DurableNonce {
+            account: nonce.address,
+            authority,
+        }),
+        ..SendOptions::default()
+    };
+    let state = state_path();
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &recipients,
+        &state,
+        false,
+        &options,
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, 30);
+    assert_eq!(distribution.signatures.len(), 3);
+    for recipient in &recipients {
+        let ata = program.ata_address(&recipient.owner, &mint.address);
+        assert_eq!(get_token_account(&client, &ata).await.unwrap().amount, 3);
+    }
+    assert_ne!(
+        get_nonce_account(&client, &nonce.address)
+            .await
+            .unwrap()
+            .nonce,
+        nonce.state.nonce
+    );
+    let sent = &controls.sent.lock().unwrap()[sent..];
+    assert_eq!(sent.len(), 3);
+    for transaction in sent {
+        assert_eq!(transaction.message.header().num_required_signatures, 2);
+    }
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn resume_does_not_resend_a_pending_batch_that_landed() {
+    resume_after_a_landed_batch(0).await;
+}
+
+#[tokio::test]
+async fn resume_finds_a_landed_batch_the_status_cache_forgot() {
+    //Only the transaction history still knows about the batch by the time the run resumes
+    resume_after_a_landed_batch(MAX_PROCESSING_AGE + 1).await;
+}
+
+/// Resume a run that died right after its first row landed, `blocks` blocks later.
+async fn resume_after_a_landed_batch(blocks: u64) {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        100,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let recipients = [
+        Recipient {
+            owner: Keypair::new().pubkey(),
+            amount: 10,
+        },
+        Recipient {
+            owner: Keypair::new().pubkey(),
+            amount: 20,
+        },
+    ];
+    let first = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &recipients[0].owner,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    //The first row landed but the run died before recording it
+    let landed = transfer_checked(
+        &client,
+        &payer,
+        program,
+        &source.address,
+        &mint.address,
+        &first.address,
+        10,
+        0,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let blockhash = *controls
+        .sent
+        .lock()
+        .unwrap()
+        .last()
+        .unwrap()
+        .message
+        .recent_blockhash();
+    {
+        let mut svm = svm.lock().unwrap();
+        for _ in 0..blocks {
+            advance_block(&mut svm);
+        }
+    }
+    let state = state_path();
+    let rows: Vec<_> = recipients
+        .iter()
+        .map(|recipient| {
+            json!({
+                "recipient": recipient.owner.to_string(),
+                "amount": recipient.amount,
+                "signature": null,
+            })
+        })
+        .collect();
+    fs::write(
+        &state,
+        json!({
+            "mint": mint.address.to_string(),
+            "rows": rows,
+            "pending": {
+                "signature": landed.signature.to_string(),
+                "blockhash": blockhash.to_string(),
+                "last_valid_block_height": 0,
+                "rows": [0],
+            },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &recipients,
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.resumed, 1);
+    assert_eq!(distribution.sent, 1);
+    assert_eq!(
+        get_token_account(&client, &first.address)
+            .await
+            .unwrap()
+            .amount,
+        10
+    );
+    assert_eq!(
+        get_token_account(&client, &source.address)
+            .await
+            .unwrap()
+            .amount,
+        70
+    );
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn distribute_refuses_a_state_file_for_another_list() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        100,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let owner = Keypair::new().pubkey();
+    let state = state_path();
+    distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &[Recipient { owner, amount: 5 }],
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &[Recipient { owner, amount: 6 }],
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn distribute_checks_the_balance_before_sending() {
+    let program = TokenProgram::Token2022;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        10,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let recipients =
+        [Pubkey::new_unique(), Pubkey::new_unique()].map(|owner| Recipient { owner, amount: 6 });
+    let state = state_path();
+
+    let result = distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &recipients,
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert!(!state.exists());
+}
@@ -0,0 +1,294 @@
+mod common;
+
+use common::{PROGRAMS, Sent, advance_block, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022_interface::{instruction::AuthorityType, state::AccountState};
+use spl_token_tools::{
+    FreezeAllError, SendOptions, TokenProgram, create_ata, create_mint, create_token_account,
+    freeze::FREEZE_BATCH_SIZE, freeze_account, freeze_all, get_token_account,
+    get_token_accounts_by_mint, mint_to, set_authority, thaw_account, transfer_checked,
+};
+
+#[tokio::test]
+async fn freeze_blocks_transfers_until_thawed() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let destination = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &Keypair::new().pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            10,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let options = SendOptions::default();
+        let transfer = || {
+            transfer_checked(
+                &client,
+                &payer,
+                program,
+                &source.address,
+                &mint.address,
+                &destination.address,
+                1,
+                0,
+                &options,
+            )
+        };
+
+        let frozen = freeze_account(
+            &client,
+            &payer,
+            program,
+            &source.address,
+            &mint.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(frozen.before, AccountState::Initialized);
+        assert_eq!(frozen.account.state, AccountState::Frozen);
+        assert!(transfer().await.is_err());
+
+        let thawed = thaw_account(
+            &client,
+            &payer,
+            program,
+            &source.address,
+            &mint.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(thawed.before, AccountState::Frozen);
+        assert_eq!(thawed.account.state, AccountState::Initialized);
+        assert_eq!(transfer().await.unwrap_sent().source.amount, 9);
+    }
+}
+
+#[tokio::test]
+async fn freeze_requires_the_freeze_authority() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let impostor = funded_keypair(&svm, 1);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = freeze_account(
+        &client,
+        &impostor,
+        program,
+        &ata.address,
+        &mint.address,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .state,
+        AccountState::Initialized
+    );
+}
+
+#[tokio::test]
+async fn freeze_all_freezes_every_holder_of_the_mint() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let other_mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let mut holders = Vec::new();
+        for _ in 0..25 {
+            let ata = create_ata(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &Keypair::new().pubkey(),
+                &SendOptions::default(),
+            )
+            .await
+            .unwrap_sent();
+            holders.push(ata.address);
+        }
+        let keypair_account = create_token_account(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        holders.push(keypair_account.address);
+        let untouched = create_ata(
+            &client,
+            &payer,
+            program,
+            &other_mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        freeze_account(
+            &client,
+            &payer,
+            program,
+            &holders[0],
+            &mint.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let changes = freeze_all(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(changes.len(), holders.len() - 1);
+        assert!(changes.iter().all(|change| {
+            change.before == AccountState::Initialized
+                && change.account.state == AccountState::Frozen
+        }));
+        let accounts = get_token_accounts_by_mint(&client, program, &mint.address)
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), holders.len());
+        assert!(
+            accounts
+                .iter()
+                .all(|(_, account)| account.state == AccountState::Frozen)
+        );
+        assert_eq!(
+            get_token_account(&client, &untouched.address)
+                .await
+                .unwrap()
+                .state,
+            AccountState::Initialized
+        );
+    }
+}
+
+#[tokio::test]
+async fn freeze_all_reports_what_it_froze_before_failing() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    for _ in 0..FREEZE_BATCH_SIZE + 5 {
+        create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &Keypair::new().pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+    }
+    //A freeze authority that can pay for the first batch only
+    let freezer = Keypair::new();
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(0)
+        .await
+        .unwrap();
+    {
+        let mut svm = svm.lock().unwrap();
+        svm.airdrop(&freezer.pubkey(), rent + 7_500).unwrap();
+        advance_block(&mut svm);
+    }
+    set_authority(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        AuthorityType::FreezeAccount,
+        Some(&freezer.pubkey()),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let err = freeze_all(
+        &client,
+        &freezer,
+        program,
+        &mint.address,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_err();
+
+    let partial = err.downcast_ref::<FreezeAllError>().unwrap();
+    assert_eq!(partial.frozen.len(), FREEZE_BATCH_SIZE);
+    for change in &partial.frozen {
+        let account = get_token_account(&client, &change.address).await.unwrap();
+        assert_eq!(account.state, AccountState::Frozen);
+    }
+    let accounts = get_token_accounts_by_mint(&client, program, &mint.address)
+        .await
+        .unwrap();
+    let frozen = accounts
+        .iter()
+        .filter(|(_, account)| account.state == AccountState::Frozen)
+        .count();
+    assert_eq!(frozen, FREEZE_BATCH_SIZE);
+}
@@ -0,0 +1,486 @@
+mod common;
+
+use common::{Controls, Sent, advance_block, funded_keypair, setup, setup_with_controls};
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use spl_token_tools::{
+    Recipient, SendOptions, TokenProgram, close_lookup_table, create_ata, create_lookup_table,
+    create_mint, deactivate_lookup_table, distribute, distribute::ROWS_PER_LOOKUP_TABLE,
+    extend_lookup_table, get_lookup_table, get_token_account, mint_to,
+    transaction::send_instructions_with_lookup_tables,
+};
+use std::{fs, path::Path};
+
+fn pubkeys(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Keypair::new().pubkey()).collect()
+}
+
+#[tokio::test]
+async fn lookup_table_lifecycle() {
+    let (client, svm) = setup();
+    let authority = funded_keypair(&svm, 10);
+    let addresses = pubkeys(30);
+
+    let created = create_lookup_table(&client, &authority, &addresses, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    assert_eq!(created.signatures.len(), 2);
+    assert_eq!(created.table.addresses, addresses);
+    assert_eq!(created.table.meta.authority, Some(authority.pubkey()));
+
+    let more = pubkeys(3);
+    let signatures = extend_lookup_table(
+        &client,
+        &authority,
+        &created.table.address,
+        &[&addresses[..2], &more].concat(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(signatures.len(), 1);
+    let table = get_lookup_table(&client, &created.table.address)
+        .await
+        .unwrap();
+    assert_eq!(table.addresses, [addresses, more].concat());
+    assert!(!table.is_deactivated());
+
+    let recipient = Keypair::new().pubkey();
+    assert!(
+        close_lookup_table(
+            &client,
+            &authority,
+            &table.address,
+            &recipient,
+            &SendOptions::default()
+        )
+        .await
+        .is_err()
+    );
+    deactivate_lookup_table(&client, &authority, &table.address, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    assert!(
+        get_lookup_table(&client, &table.address)
+            .await
+            .unwrap()
+            .is_deactivated()
+    );
+    //Closing waits for the deactivation slot to leave the slot hashes
+    assert!(
+        close_lookup_table(
+            &client,
+            &authority,
+            &table.address,
+            &recipient,
+            &SendOptions::default()
+        )
+        .await
+        .is_err()
+    );
+    {
+        let mut svm = svm.lock().unwrap();
+        for _ in 0..=512 {
+            advance_block(&mut svm);
+        }
+    }
+    close_lookup_table(
+        &client,
+        &authority,
+        &table.address,
+        &recipient,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert!(client.get_balance(&recipient).await.unwrap() > 0);
+    assert!(get_lookup_table(&client, &table.address).await.is_err());
+}
+
+#[tokio::test]
+async fn v0_transactions_load_accounts_from_the_table() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    //More recipients than fit in a legacy transaction
+    let recipients = pubkeys(40);
+    let table = create_lookup_table(&client, &payer, &recipients, &SendOptions::default())
+        .await
+        .unwrap_sent()
+        .table;
+    let transfers: Vec<_> = recipients
+        .iter()
+        .map(|recipient| transfer(&payer.pubkey(), recipient, 1_000_000))
+        .collect();
+
+    send_instructions_with_lookup_tables(
+        &client,
+        &transfers,
+        &payer.pubkey(),
+        &[&payer],
+        &[table.account()],
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    for recipient in &recipients {
+        assert_eq!(client.get_balance(recipient).await.unwrap(), 1_000_000);
+    }
+    let sent = controls.sent.lock().unwrap();
+    let VersionedMessage::V0(message) = &sent.last().unwrap().message else {
+        panic!("expected a v0 transaction");
+    };
+    assert_eq!(message.address_table_lookups[0].account_key, table.address);
+    assert_eq!(message.address_table_lookups[0].writable_indexes.len(), 40);
+}
+
+/// A mint with a funded source ATA, and `rows` recipients that already hold an ATA of it.
+async fn distribution_setup(rows: usize) -> (RpcClient, Controls, Keypair, Pubkey, Vec<Recipient>) {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        10_000,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let recipients: Vec<Recipient> = pubkeys(rows)
+        .into_iter()
+        .map(|owner| Recipient { owner, amount: 7 })
+        .collect();
+    for recipient in &recipients {
+        create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &recipient.owner,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+    }
+    (client, controls, payer, mint.address, recipients)
+}
+
+/// The state of a run that recorded `table` for the first chunk and delivered the first
+/// `delivered` rows.
+fn write_state(
+    path: &Path,
+    mint: &Pubkey,
+    recipients: &[Recipient],
+    delivered: usize,
+    table: &Pubkey,
+) {
+    let rows: Vec<_> = recipients
+        .iter()
+        .enumerate()
+        .map(|(row, recipient)| {
+            json!({
+                "recipient": recipient.owner.to_string(),
+                "amount": recipient.amount,
+                "signature": (row < delivered).then(|| Signature::default().to_string()),
+            })
+        })
+        .collect();
+    fs::write(
+        path,
+        json!({
+            "mint": mint.to_string(),
+            "rows": rows,
+            "pending": null,
+            "lookup_tables": { "0": table.to_string() },
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn distributions_stay_legacy_unless_asked_for_lookup_tables() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, recipients) =
+        distribution_setup(ROWS_PER_LOOKUP_TABLE).await;
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint,
+        &recipients,
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, ROWS_PER_LOOKUP_TABLE);
+    assert!(distribution.lookup_tables.is_empty());
+    let sent = &controls.sent.lock().unwrap()[sent..];
+    assert_eq!(sent.len(), 7);
+    assert_eq!(distribution.signatures.len(), 7);
+    assert!(
+        sent.iter()
+            .all(|transaction| matches!(transaction.message, VersionedMessage::Legacy(_)))
+    );
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn distributions_go_through_a_lookup_table_when_asked() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, recipients) =
+        distribution_setup(ROWS_PER_LOOKUP_TABLE).await;
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint,
+        &recipients,
+        &state,
+        true,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, ROWS_PER_LOOKUP_TABLE);
+    assert_eq!(distribution.lookup_tables.len(), 1);
+    let table = get_lookup_table(&client, &distribution.lookup_tables[0])
+        .await
+        .unwrap();
+    assert!(table.is_deactivated());
+    //The source, the mint and the ATAs, the recipients are not referenced
+    assert_eq!(table.addresses.len(), ROWS_PER_LOOKUP_TABLE + 2);
+    for recipient in &recipients {
+        let ata = program.ata_address(&recipient.owner, &mint);
+        assert_eq!(get_token_account(&client, &ata).await.unwrap().amount, 7);
+    }
+    //3 batches instead of 7, plus creating the table, 6 extensions and the deactivation
+    let sent = &controls.sent.lock().unwrap()[sent..];
+    let batches = sent
+        .iter()
+        .filter(|transaction| {
+            matches!(&transaction.message, VersionedMessage::V0(message) if !message.address_table_lookups.is_empty())
+        })
+        .count();
+    assert_eq!(batches, 3);
+    assert_eq!(sent.len(), 11);
+    assert_eq!(distribution.signatures.len(), 11);
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn distributions_skip_a_lookup_table_that_packs_no_fewer_batches() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, _) = distribution_setup(0).await;
+    //Rows creating an ATA run 6 instructions each, so the trace holds 10 a transaction table or not
+    let recipients: Vec<Recipient> = pubkeys(11)
+        .into_iter()
+        .map(|owner| Recipient { owner, amount: 7 })
+        .collect();
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint,
+        &recipients,
+        &state,
+        true,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, 11);
+    assert_eq!(distribution.created_accounts, 11);
+    assert!(distribution.lookup_tables.is_empty());
+    let sent = &controls.sent.lock().unwrap()[sent..];
+    assert_eq!(sent.len(), 2);
+    assert_eq!(distribution.signatures.len(), 2);
+    assert!(
+        sent.iter()
+            .all(|transaction| matches!(transaction.message, VersionedMessage::Legacy(_)))
+    );
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn resumed_distributions_finish_with_their_lookup_table() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, recipients) =
+        distribution_setup(ROWS_PER_LOOKUP_TABLE).await;
+    let source = program.ata_address(&payer.pubkey(), &mint);
+    //An interrupted run that made and filled a table, then sent nothing
+    let mut addresses = vec![source, mint];
+    addresses.extend(
+        recipients
+            .iter()
+            .map(|recipient| program.ata_address(&recipient.owner, &mint)),
+    );
+    let table = create_lookup_table(&client, &payer, &addresses, &SendOptions::default())
+        .await
+        .unwrap_sent()
+        .table;
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    write_state(&state, &mint, &recipients, 0, &table.address);
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint,
+        &recipients,
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, ROWS_PER_LOOKUP_TABLE);
+    assert_eq!(distribution.lookup_tables, vec![table.address]);
+    assert!(
+        get_lookup_table(&client, &table.address)
+            .await
+            .unwrap()
+            .is_deactivated()
+    );
+    for recipient in &recipients {
+        let ata = program.ata_address(&recipient.owner, &mint);
+        assert_eq!(get_token_account(&client, &ata).await.unwrap().amount, 7);
+    }
+    //The table is paid for already: 3 batches and the deactivation, instead of 7 legacy ones
+    let sent = &controls.sent.lock().unwrap()[sent..];
+    let batches = sent
+        .iter()
+        .filter(|transaction| {
+            matches!(&transaction.message, VersionedMessage::V0(message) if !message.address_table_lookups.is_empty())
+        })
+        .count();
+    assert_eq!(batches, 3);
+    assert_eq!(sent.len(), 4);
+    assert_eq!(distribution.signatures.len(), 4);
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn resumed_distributions_deactivate_a_lookup_table_they_no_longer_need() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, recipients) = distribution_setup(3).await;
+    let table = create_lookup_table(&client, &payer, &[mint], &SendOptions::default())
+        .await
+        .unwrap_sent()
+        .table;
+    //The rows left fit a single legacy transaction
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    write_state(&state, &mint, &recipients, 1, &table.address);
+    let sent = controls.sent.lock().unwrap().len();
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint,
+        &recipients,
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, 2);
+    assert_eq!(distribution.lookup_tables, vec![table.address]);
+    assert!(
+        get_lookup_table(&client, &table.address)
+            .await
+            .unwrap()
+            .is_deactivated()
+    );
+    //The batch and the deactivation
+    assert_eq!(controls.sent.lock().unwrap().len() - sent, 2);
+    assert_eq!(distribution.signatures.len(), 2);
+    fs::remove_file(state).unwrap();
+}
+
+#[tokio::test]
+async fn finished_distributions_deactivate_a_lookup_table_left_active() {
+    let program = TokenProgram::Token;
+    let (client, controls, payer, mint, recipients) = distribution_setup(3).await;
+    let table = create_lookup_table(&client, &payer, &[mint], &SendOptions::default())
+        .await
+        .unwrap_sent()
+        .table;
+    //Interrupted after the last batch, before the deactivation
+    let state = std::env::temp_dir().join(format!("lookup-{mint}.json"));
+    write_state(&state, &mint, &recipients, recipients.len(), &table.address);
+    let sent = controls.sent.lock().unwrap().len();
+    let options = SendOptions::default();
+    let run = || {
+        distribute(
+            &client,
+            &payer,
+            program,
+            &mint,
+            &recipients,
+            &state,
+            false,
+            &options,
+        )
+    };
+
+    let distribution = run().await.unwrap_sent();
+
+    assert_eq!(distribution.sent, 0);
+    assert_eq!(distribution.resumed, 3);
+    assert_eq!(distribution.lookup_tables, vec![table.address]);
+    assert_eq!(distribution.signatures.len(), 1);
+    assert_eq!(controls.sent.lock().unwrap().len() - sent, 1);
+    assert!(
+        get_lookup_table(&client, &table.address)
+            .await
+            .unwrap()
+            .is_deactivated()
+    );
+    //Done with, the table is not deactivated or reported again
+    let rerun = run().await.unwrap_sent();
+    assert!(rerun.lookup_tables.is_empty());
+    assert!(rerun.signatures.is_empty());
+    fs::remove_file(state).unwrap();
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token_metadata_interface::state::Field;
+use spl_token_tools::{
+    CreatedMint, Metadata, MetadataPointer, MintExtensions, SendOptions, TokenProgram,
+    create_mint_with_extensions, get_metadata, update_metadata,
+};
+
+async fn create_mint_with_metadata(client: &RpcClient, payer: &Keypair) -> CreatedMint {
+    let mint = Keypair::new();
+    let extensions = MintExtensions {
+        metadata_pointer: Some(MetadataPointer {
+            authority: Some(payer.pubkey()),
+            metadata_address: Some(mint.pubkey()),
+        }),
+        metadata: Some(Metadata {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://example.com/test.json".to_string(),
+            additional_metadata: vec![("website".to_string(), "example.com".to_string())],
+        }),
+        ..MintExtensions::default()
+    };
+    create_mint_with_extensions(
+        client,
+        payer,
+        &mint,
+        TokenProgram::Token2022,
+        6,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent()
+}
+
+async fn assert_rent_exempt(client: &RpcClient, address: &Pubkey) {
+    let account = client.get_account(address).await.unwrap();
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(account.data.len())
+        .await
+        .unwrap();
+    assert!(account.lamports >= rent);
+}
+
+#[tokio::test]
+async fn create_mint_writes_metadata_on_the_mint() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+
+    let mint = create_mint_with_metadata(&client, &payer).await;
+
+    let metadata = get_metadata(&client, &mint.address).await.unwrap().unwrap();
+    assert_eq!(metadata.mint, mint.address);
+    assert_eq!(
+        Option::<Pubkey>::from(metadata.update_authority),
+        Some(payer.pubkey())
+    );
+    assert_eq!(metadata.name, "Test Token");
+    assert_eq!(metadata.symbol, "TEST");
+    assert_eq!(metadata.uri, "https://example.com/test.json");
+    assert_eq!(
+        metadata.additional_metadata,
+        [("website".to_string(), "example.com".to_string())]
+    );
+    assert_rent_exempt(&client, &mint.address).await;
+}
+
+#[tokio::test]
+async fn update_metadata_grows_the_mint_and_tops_up_rent() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint_with_metadata(&client, &payer).await;
+    let len_before = client.get_account(&mint.address).await.unwrap().data.len();
+
+    let updated = update_metadata(
+        &client,
+        &payer,
+        &mint.address,
+        Field::Name,
+        "A much longer name for the test token".to_string(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        updated.metadata.name,
+        "A much longer name for the test token"
+    );
+
+    let updated = update_metadata(
+        &client,
+        &payer,
+        &mint.address,
+        Field::Key("website".to_string()),
+        "example.org".to_string(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        updated.metadata.additional_metadata,
+        [("website".to_string(), "example.org".to_string())]
+    );
+    assert_eq!(updated.metadata.symbol, "TEST");
+
+    let account = client.get_account(&mint.address).await.unwrap();
+    assert!(account.data.len() > len_before);
+    assert_rent_exempt(&client, &mint.address).await;
+}
+
+#[tokio::test]
+async fn update_metadata_requires_the_update_authority() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let impostor = funded_keypair(&svm, 1);
+    let mint = create_mint_with_metadata(&client, &payer).await;
+
+    let result = update_metadata(
+        &client,
+        &impostor,
+        &mint.address,
+        Field::Name,
+        "Stolen".to_string(),
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        get_metadata(&client, &mint.address)
+            .await
+            .unwrap()
+            .unwrap()
+            .name,
+        "Test Token"
+    );
+}
+
+#[tokio::test]
+async fn metadata_needs_a_pointer_to_the_mint() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let extensions = MintExtensions {
+        metadata: Some(Metadata {
+            name: "Test Token".to_string(),
+            ..Metadata::default()
+        }),
+        ..MintExtensions::default()
+    };
+
+    let result = create_mint_with_extensions(
+        &client,
+        &payer,
+        &Keypair::new(),
+        TokenProgram::Token2022,
+        6,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
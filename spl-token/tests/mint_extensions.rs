@@ -0,0 +1,239 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022_interface::{
+    extension::{
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        default_account_state::DefaultAccountState, interest_bearing_mint::InterestBearingConfig,
+        metadata_pointer::MetadataPointer as MetadataPointerExtension,
+        mint_close_authority::MintCloseAuthority, permanent_delegate::PermanentDelegate,
+        transfer_fee::TransferFeeConfig,
+    },
+    state::{AccountState, Mint},
+};
+use spl_token_tools::{
+    InterestRate, MetadataPointer, MintExtensions, SendOptions, TokenProgram, TransferFee,
+    create_ata, create_mint_with_extensions, get_mint_extensions, mint_to, transfer_checked,
+};
+
+#[tokio::test]
+async fn create_mint_sizes_and_initializes_every_extension() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = Keypair::new();
+    let delegate = Keypair::new().pubkey();
+    let extensions = MintExtensions {
+        transfer_fee: Some(TransferFee {
+            basis_points: 50,
+            maximum_fee: 5_000,
+            config_authority: Some(payer.pubkey()),
+            withdraw_authority: Some(payer.pubkey()),
+        }),
+        interest_rate: Some(InterestRate {
+            rate: 250,
+            authority: Some(payer.pubkey()),
+        }),
+        non_transferable: false,
+        permanent_delegate: Some(delegate),
+        close_authority: Some(payer.pubkey()),
+        default_account_state: Some(AccountState::Initialized),
+        metadata_pointer: Some(MetadataPointer {
+            authority: Some(payer.pubkey()),
+            metadata_address: Some(mint.pubkey()),
+        }),
+        metadata: None,
+    };
+
+    let created = create_mint_with_extensions(
+        &client,
+        &payer,
+        &mint,
+        TokenProgram::Token2022,
+        6,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(created.address, mint.pubkey());
+    assert_eq!(created.state.decimals, 6);
+    assert_eq!(created.extensions, extensions.extension_types());
+    assert_eq!(
+        get_mint_extensions(&client, &mint.pubkey()).await.unwrap(),
+        extensions.extension_types()
+    );
+
+    let account = client.get_account(&mint.pubkey()).await.unwrap();
+    assert_eq!(account.data.len(), extensions.mint_len().unwrap());
+    assert_eq!(
+        account.lamports,
+        client
+            .get_minimum_balance_for_rent_exemption(account.data.len())
+            .await
+            .unwrap()
+    );
+
+    let state = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+    let fee = state.get_extension::<TransferFeeConfig>().unwrap();
+    assert_eq!(
+        u16::from(fee.newer_transfer_fee.transfer_fee_basis_points),
+        50
+    );
+    assert_eq!(u64::from(fee.newer_transfer_fee.maximum_fee), 5_000);
+    let interest = state.get_extension::<InterestBearingConfig>().unwrap();
+    assert_eq!(i16::from(interest.current_rate), 250);
+    assert_eq!(
+        Option::<_>::from(state.get_extension::<PermanentDelegate>().unwrap().delegate),
+        Some(delegate)
+    );
+    assert_eq!(
+        Option::<_>::from(
+            state
+                .get_extension::<MintCloseAuthority>()
+                .unwrap()
+                .close_authority
+        ),
+        Some(payer.pubkey())
+    );
+    assert_eq!(
+        state.get_extension::<DefaultAccountState>().unwrap().state,
+        AccountState::Initialized as u8
+    );
+    assert_eq!(
+        Option::<_>::from(
+            state
+                .get_extension::<MetadataPointerExtension>()
+                .unwrap()
+                .metadata_address
+        ),
+        Some(mint.pubkey())
+    );
+}
+
+#[tokio::test]
+async fn extensions_require_token_2022() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let extensions = MintExtensions {
+        non_transferable: true,
+        ..MintExtensions::default()
+    };
+
+    let result = create_mint_with_extensions(
+        &client,
+        &payer,
+        &Keypair::new(),
+        TokenProgram::Token,
+        9,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn default_frozen_state_applies_to_new_accounts() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let extensions = MintExtensions {
+        default_account_state: Some(AccountState::Frozen),
+        ..MintExtensions::default()
+    };
+    let mint = create_mint_with_extensions(
+        &client,
+        &payer,
+        &Keypair::new(),
+        TokenProgram::Token2022,
+        9,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(mint.extensions, [ExtensionType::DefaultAccountState]);
+
+    let ata = create_ata(
+        &client,
+        &payer,
+        TokenProgram::Token2022,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(ata.state.state, AccountState::Frozen);
+}
+
+#[tokio::test]
+async fn non_transferable_mint_rejects_transfers() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let program = TokenProgram::Token2022;
+    let extensions = MintExtensions {
+        non_transferable: true,
+        ..MintExtensions::default()
+    };
+    let mint = create_mint_with_extensions(
+        &client,
+        &payer,
+        &Keypair::new(),
+        program,
+        2,
+        &extensions,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let destination = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &Keypair::new().pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        100,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let result = transfer_checked(
+        &client,
+        &payer,
+        program,
+        &source.address,
+        &mint.address,
+        &destination.address,
+        10,
+        2,
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
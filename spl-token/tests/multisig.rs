@@ -0,0 +1,225 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_2022_interface::instruction::AuthorityType;
+use spl_token_tools::{
+    SendOptions, TokenProgram, create_ata, create_mint, create_multisig, mint_to,
+    mint_to_with_signers, set_authority, transfer_checked_with_signers,
+};
+
+#[tokio::test]
+async fn multisig_mint_authority_needs_m_signatures() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let pubkeys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let multisig = create_multisig(
+            &client,
+            &payer,
+            program,
+            2,
+            &pubkeys,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(multisig.state.m, 2);
+        assert_eq!(multisig.state.n, 3);
+        assert_eq!(&multisig.state.signers[..3], &pubkeys[..]);
+
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        set_authority(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            AuthorityType::MintTokens,
+            Some(&multisig.address),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let minted = mint_to_with_signers(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            &multisig.address,
+            &[&signers[0], &signers[2]],
+            500,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(minted.account.amount, 500);
+
+        assert!(
+            mint_to_with_signers(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &ata.address,
+                &multisig.address,
+                &[&signers[1]],
+                1,
+                &SendOptions::default()
+            )
+            .await
+            .is_err()
+        );
+        assert!(
+            mint_to(
+                &client,
+                &payer,
+                program,
+                &mint.address,
+                &ata.address,
+                1,
+                &SendOptions::default()
+            )
+            .await
+            .is_err()
+        );
+    }
+}
+
+#[tokio::test]
+async fn multisig_owned_account_transfers_with_signers() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let signers = [Keypair::new(), Keypair::new()];
+        let pubkeys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let multisig = create_multisig(
+            &client,
+            &payer,
+            program,
+            2,
+            &pubkeys,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &multisig.address,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let destination = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &Keypair::new().pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            1_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert!(
+            transfer_checked_with_signers(
+                &client,
+                &payer,
+                program,
+                &source.address,
+                &mint.address,
+                &destination.address,
+                &multisig.address,
+                &[&signers[0]],
+                100,
+                2,
+                &SendOptions::default()
+            )
+            .await
+            .is_err()
+        );
+
+        let transfer = transfer_checked_with_signers(
+            &client,
+            &payer,
+            program,
+            &source.address,
+            &mint.address,
+            &destination.address,
+            &multisig.address,
+            &[&signers[0], &signers[1]],
+            100,
+            2,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(transfer.source.amount, 900);
+        assert_eq!(transfer.destination.amount, 100);
+    }
+}
+
+#[tokio::test]
+async fn create_multisig_rejects_bad_thresholds() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let pubkeys = [Keypair::new().pubkey(), Keypair::new().pubkey()];
+
+    for m in [0, 3] {
+        assert!(
+            create_multisig(
+                &client,
+                &payer,
+                TokenProgram::Token,
+                m,
+                &pubkeys,
+                &SendOptions::default()
+            )
+            .await
+            .is_err()
+        );
+    }
+    assert!(
+        create_multisig(
+            &client,
+            &payer,
+            TokenProgram::Token,
+            1,
+            &[],
+            &SendOptions::default()
+        )
+        .await
+        .is_err()
+    );
+}
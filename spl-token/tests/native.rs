@@ -0,0 +1,122 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token_tools::{
+    SendOptions, create_ata, get_token_account, transfer_checked, unwrap_sol, wrap_sol,
+};
+
+#[tokio::test]
+async fn wrap_twice_then_unwrap_everything() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let owner = funded_keypair(&svm, 10);
+        let destination = Keypair::new().pubkey();
+
+        let wrapped = wrap_sol(
+            &client,
+            &owner,
+            program,
+            1_000_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(wrapped.account.mint, program.native_mint());
+        assert!(wrapped.account.is_native());
+        assert_eq!(wrapped.account.amount, 1_000_000_000);
+
+        let wrapped = wrap_sol(
+            &client,
+            &owner,
+            program,
+            500_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(wrapped.account.amount, 1_500_000_000);
+        let rent = client.get_balance(&wrapped.address).await.unwrap() - 1_500_000_000;
+
+        let closed = unwrap_sol(
+            &client,
+            &owner,
+            program,
+            &destination,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(closed.address, wrapped.address);
+        assert_eq!(closed.lamports, 1_500_000_000 + rent);
+        assert_eq!(
+            client.get_balance(&destination).await.unwrap(),
+            closed.lamports
+        );
+    }
+}
+
+#[tokio::test]
+async fn wrapped_sol_moves_with_transfer_checked() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let owner = funded_keypair(&svm, 10);
+        let recipient = funded_keypair(&svm, 1);
+        let mint = program.native_mint();
+        let wrapped = wrap_sol(
+            &client,
+            &owner,
+            program,
+            2_000_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let destination = create_ata(
+            &client,
+            &owner,
+            program,
+            &mint,
+            &recipient.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let transfer = transfer_checked(
+            &client,
+            &owner,
+            program,
+            &wrapped.address,
+            &mint,
+            &destination.address,
+            750_000_000,
+            9,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(transfer.source.amount, 1_250_000_000);
+        assert_eq!(transfer.destination.amount, 750_000_000);
+
+        let balance_before = client.get_balance(&recipient.pubkey()).await.unwrap();
+        let closed = unwrap_sol(
+            &client,
+            &recipient,
+            program,
+            &recipient.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert!(closed.lamports > 750_000_000);
+        assert!(
+            client.get_balance(&recipient.pubkey()).await.unwrap() > balance_before + 749_000_000
+        );
+        assert!(
+            get_token_account(&client, &destination.address)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup, setup_with_controls};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_system_interface::instruction::transfer;
+use spl_token_tools::{
+    DurableNonce, SendOptions, TokenProgram, advance_nonce, authorize_nonce_account, create_ata,
+    create_mint, create_nonce_account, get_nonce_account, get_token_account, mint_to,
+    transaction::send_instructions, withdraw_from_nonce_account,
+};
+use std::sync::{Arc, atomic::Ordering};
+
+#[tokio::test]
+async fn nonce_account_lifecycle() {
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+
+    let created = create_nonce_account(&client, &payer, &payer.pubkey(), &SendOptions::default())
+        .await
+        .unwrap_sent();
+    assert_eq!(created.state.authority, payer.pubkey());
+    assert!(created.state.lamports > 0);
+
+    let advanced = advance_nonce(&client, &payer, &created.address, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    assert_ne!(advanced.nonce, created.state.nonce);
+
+    let new_authority = funded_keypair(&svm, 1);
+    authorize_nonce_account(
+        &client,
+        &payer,
+        &created.address,
+        &new_authority.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        get_nonce_account(&client, &created.address)
+            .await
+            .unwrap()
+            .authority,
+        new_authority.pubkey()
+    );
+    assert!(
+        advance_nonce(&client, &payer, &created.address, &SendOptions::default())
+            .await
+            .is_err()
+    );
+
+    let destination = Keypair::new().pubkey();
+    withdraw_from_nonce_account(
+        &client,
+        &new_authority,
+        &created.address,
+        &destination,
+        created.state.lamports,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    assert_eq!(
+        client.get_balance(&destination).await.unwrap(),
+        created.state.lamports
+    );
+    assert!(get_nonce_account(&client, &created.address).await.is_err());
+}
+
+#[tokio::test]
+async fn nonce_transactions_outlive_their_blockhash() {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let authority = Arc::new(funded_keypair(&svm, 1));
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let nonce = create_nonce_account(
+        &client,
+        &payer,
+        &authority.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    let options = SendOptions {
+        nonce: Some(DurableNonce {
+            account: nonce.address,
+            authority: authority.clone(),
+        }),
+        ..SendOptions::default()
+    };
+    //Lost on the way, the transaction is only rebroadcast after its blockhash would be gone
+    controls.drops.store(1, Ordering::SeqCst);
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        5,
+        &options,
+    )
+    .await
+    .unwrap_sent();
+    let sent = controls.sent.lock().unwrap().clone();
+    let used = get_nonce_account(&client, &nonce.address).await;
+    //Advancing writes the nonce account, so it goes out with a recent blockhash
+    let advanced = advance_nonce(&client, &authority, &nonce.address, &options).await;
+
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .amount,
+        5
+    );
+    let transaction = sent.last().unwrap();
+    assert_eq!(*transaction.message.recent_blockhash(), nonce.state.nonce);
+    let message = &transaction.message;
+    let advance = &message.instructions()[0];
+    assert_eq!(
+        message.static_account_keys()[advance.program_id_index as usize],
+        solana_system_interface::program::ID
+    );
+    assert_eq!(message.header().num_required_signatures, 2);
+    let used = used.unwrap().nonce;
+    assert_ne!(used, nonce.state.nonce);
+    assert_ne!(advanced.unwrap_sent().nonce, used);
+}
+
+#[tokio::test]
+async fn nonce_transactions_that_never_land_are_given_up_on() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let nonce = create_nonce_account(&client, &payer, &payer.pubkey(), &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let options = SendOptions {
+        nonce: Some(DurableNonce {
+            account: nonce.address,
+            authority: Arc::new(payer.insecure_clone()),
+        }),
+        ..SendOptions::default()
+    };
+    let recipient = Keypair::new().pubkey();
+    //Every broadcast is lost, while the nonce never expires on its own
+    controls.drops.store(usize::MAX, Ordering::SeqCst);
+
+    let result = send_instructions(
+        &client,
+        &[transfer(&payer.pubkey(), &recipient, 1_000_000)],
+        &payer.pubkey(),
+        &[&payer],
+        &options,
+    )
+    .await;
+    controls.drops.store(0, Ordering::SeqCst);
+
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("may still land"), "{err}");
+    assert!(controls.sent.lock().unwrap().len() < 10);
+    assert_eq!(
+        get_nonce_account(&client, &nonce.address)
+            .await
+            .unwrap()
+            .nonce,
+        nonce.state.nonce
+    );
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 0);
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use common::{MAX_PROCESSING_AGE, Sent, advance_block, funded_keypair, setup_with_controls};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use solana_system_interface::instruction::transfer;
+use spl_token_tools::{
+    DurableNonce, ExportFormat, ExportedTransaction, KeypairSource, SendMode, SendOptions,
+    SendOutcome, TokenProgram, broadcast, create_ata, create_lookup_table, create_mint,
+    create_nonce_account, get_token_account, mint_to_with_signers, transaction::send_instructions,
+};
+use std::{slice, sync::Arc};
+
+/// The transaction a call exported instead of sending.
+fn exported<T>(outcome: SendOutcome<T>) -> ExportedTransaction {
+    match outcome {
+        SendOutcome::Exported(exported) => exported,
+        _ => panic!("the transaction was not exported"),
+    }
+}
+
+#[tokio::test]
+async fn offline_authority_signs_a_nonce_transaction_later() {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    //The mint authority, kept away from the machine building the transaction
+    let offline = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &offline, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let nonce = create_nonce_account(&client, &payer, &payer.pubkey(), &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source: KeypairSource = format!("pubkey:{}", offline.pubkey()).parse().unwrap();
+    assert!(source.signer(false).is_err());
+
+    let options = SendOptions {
+        nonce: Some(DurableNonce {
+            account: nonce.address,
+            authority: Arc::new(payer.insecure_clone()),
+        }),
+        mode: SendMode::SignOnly,
+        ..SendOptions::default()
+    };
+    let authority = source.signer(true).unwrap();
+    let minted = mint_to_with_signers(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        &offline.pubkey(),
+        &[&authority],
+        5,
+        &options,
+    )
+    .await
+    .unwrap();
+
+    let exported = exported(minted);
+    assert_eq!(exported.missing_signers(), vec![offline.pubkey()]);
+    assert_eq!(
+        *exported.transaction.message.recent_blockhash(),
+        nonce.state.nonce
+    );
+    let sent = controls.sent.lock().unwrap().len();
+    assert!(broadcast(&client, &exported).await.is_err());
+
+    //Carried over as JSON, signed on the other machine and carried back
+    let mut carried =
+        ExportedTransaction::parse(&exported.export(ExportFormat::Json).unwrap()).unwrap();
+    carried.sign(&[&offline]).unwrap();
+    assert!(carried.is_complete());
+    let carried = ExportedTransaction::parse(&carried.to_base64().unwrap()).unwrap();
+
+    //The nonce outlives any recent blockhash
+    {
+        let mut svm = svm.lock().unwrap();
+        for _ in 0..=MAX_PROCESSING_AGE {
+            advance_block(&mut svm);
+        }
+    }
+    broadcast(&client, &carried).await.unwrap();
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .amount,
+        5
+    );
+    assert_eq!(controls.sent.lock().unwrap().len(), sent + 1);
+}
+
+#[tokio::test]
+async fn exported_transactions_expire_with_their_blockhash() {
+    let (client, svm, _) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let recipient = Keypair::new().pubkey();
+    let instruction = transfer(&payer.pubkey(), &recipient, 1_000_000);
+    let options = SendOptions {
+        mode: SendMode::SignOnly,
+        ..SendOptions::default()
+    };
+
+    let first = exported(
+        send_instructions(
+            &client,
+            slice::from_ref(&instruction),
+            &payer.pubkey(),
+            &[&payer],
+            &options,
+        )
+        .await
+        .unwrap(),
+    );
+    assert!(first.is_complete());
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 0);
+
+    broadcast(&client, &first).await.unwrap();
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 1_000_000);
+
+    let second = exported(
+        send_instructions(
+            &client,
+            &[instruction],
+            &payer.pubkey(),
+            &[&payer],
+            &options,
+        )
+        .await
+        .unwrap(),
+    );
+    let mut tampered = second.clone();
+    assert!(tampered.sign(&[&Keypair::new()]).is_err());
+    tampered.transaction.signatures[0] = Signature::new_unique();
+    assert!(broadcast(&client, &tampered).await.is_err());
+
+    {
+        let mut svm = svm.lock().unwrap();
+        for _ in 0..=MAX_PROCESSING_AGE {
+            advance_block(&mut svm);
+        }
+    }
+    let expired = broadcast(&client, &second).await.unwrap_err();
+    assert!(expired.to_string().contains("expired"), "{expired}");
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 1_000_000);
+}
+
+#[tokio::test]
+async fn sign_only_mode_refuses_several_transactions() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let options = SendOptions {
+        mode: SendMode::SignOnly,
+        ..SendOptions::default()
+    };
+    let addresses: Vec<Pubkey> = (0..26).map(|_| Pubkey::new_unique()).collect();
+
+    let created = create_lookup_table(&client, &payer, &addresses, &options).await;
+
+    assert!(created.is_err());
+    let exported = exported(
+        create_lookup_table(&client, &payer, &addresses[..25], &options)
+            .await
+            .unwrap(),
+    );
+    assert!(exported.is_complete());
+    assert!(controls.sent.lock().unwrap().is_empty());
+}
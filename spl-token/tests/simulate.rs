@@ -0,0 +1,248 @@
+mod common;
+
+use common::{Sent, funded_keypair, setup, setup_with_controls};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token_2022_interface::instruction::{close_account, mint_to as mint_to_instruction};
+use spl_token_tools::{
+    PostState, SendMode, SendOptions, SendOutcome, TokenProgram, create_ata, create_lookup_table,
+    create_mint, get_token_account, mint_to, simulate, transfer_checked,
+};
+
+#[tokio::test]
+async fn simulation_predicts_the_post_state_without_sending() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let instruction = mint_to_instruction(
+        &program.id(),
+        &mint.address,
+        &ata.address,
+        &payer.pubkey(),
+        &[],
+        500,
+    )
+    .unwrap();
+
+    let simulation = simulate(&client, &[instruction], &payer.pubkey(), &[&payer])
+        .await
+        .unwrap();
+
+    assert!(simulation.succeeded(), "{:?}", simulation.err);
+    assert!(simulation.units_consumed.unwrap() > 0);
+    assert!(!simulation.logs.is_empty());
+    assert_eq!(simulation.mint(&mint.address).unwrap().supply, 500);
+    let account = simulation
+        .accounts
+        .iter()
+        .find_map(|(address, state)| match state {
+            PostState::TokenAccount(account) if *address == ata.address => Some(account),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(account.amount, 500);
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .amount,
+        0
+    );
+}
+
+#[tokio::test]
+async fn failed_simulation_carries_the_error_and_logs() {
+    let program = TokenProgram::Token;
+    let (client, svm) = setup();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let overdraft = spl_token_2022_interface::instruction::transfer_checked(
+        &program.id(),
+        &ata.address,
+        &mint.address,
+        &ata.address,
+        &payer.pubkey(),
+        &[],
+        1,
+        0,
+    )
+    .unwrap();
+
+    let simulation = simulate(&client, &[overdraft], &payer.pubkey(), &[&payer])
+        .await
+        .unwrap();
+
+    assert!(!simulation.succeeded());
+    assert!(
+        simulation
+            .logs
+            .iter()
+            .any(|log| log.contains("insufficient funds")),
+        "{:?}",
+        simulation.logs
+    );
+    assert!(simulation.accounts.is_empty());
+}
+
+#[tokio::test]
+async fn closed_accounts_are_reported() {
+    for program in [TokenProgram::Token, TokenProgram::Token2022] {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let close = close_account(
+            &program.id(),
+            &ata.address,
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &[],
+        )
+        .unwrap();
+
+        let simulation = simulate(&client, &[close], &payer.pubkey(), &[&payer])
+            .await
+            .unwrap();
+
+        assert!(simulation.succeeded(), "{:?}", simulation.err);
+        assert_eq!(simulation.accounts, vec![(ata.address, PostState::Closed)]);
+    }
+}
+
+#[tokio::test]
+async fn simulate_only_mode_sends_nothing() {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let ata = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let sent = controls.sent.lock().unwrap().len();
+    let options = SendOptions {
+        mode: SendMode::Simulate,
+        ..SendOptions::default()
+    };
+
+    let minted = mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &ata.address,
+        9,
+        &options,
+    )
+    .await
+    .unwrap();
+    let overdraft = transfer_checked(
+        &client,
+        &payer,
+        program,
+        &ata.address,
+        &mint.address,
+        &Keypair::new().pubkey(),
+        10,
+        0,
+        &options,
+    )
+    .await
+    .unwrap();
+
+    let SendOutcome::Simulated(simulation) = minted else {
+        panic!("the mint was sent");
+    };
+    assert!(simulation.succeeded());
+    assert!(simulation.accounts.contains(&(
+        ata.address,
+        PostState::TokenAccount({
+            let mut account = ata.state;
+            account.amount = 9;
+            account
+        })
+    )));
+    let SendOutcome::Simulated(simulation) = overdraft else {
+        panic!("the transfer was sent");
+    };
+    assert!(!simulation.succeeded());
+    assert_eq!(controls.sent.lock().unwrap().len(), sent);
+    assert_eq!(
+        get_token_account(&client, &ata.address)
+            .await
+            .unwrap()
+            .amount,
+        0
+    );
+}
+
+#[tokio::test]
+async fn simulate_only_mode_refuses_several_transactions() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let options = SendOptions {
+        mode: SendMode::Simulate,
+        ..SendOptions::default()
+    };
+    let addresses: Vec<Pubkey> = (0..26).map(|_| Pubkey::new_unique()).collect();
+
+    let created = create_lookup_table(&client, &payer, &addresses, &options).await;
+
+    assert!(created.is_err());
+    assert!(controls.sent.lock().unwrap().is_empty());
+    let SendOutcome::Simulated(simulation) =
+        create_lookup_table(&client, &payer, &addresses[..25], &options)
+            .await
+            .unwrap()
+    else {
+        panic!("the table was created");
+    };
+    assert!(simulation.succeeded(), "{:?}", simulation.err);
+    assert!(controls.sent.lock().unwrap().is_empty());
+}
@@ -0,0 +1,416 @@
+mod common;
+
+use common::{PROGRAMS, Sent, funded_keypair, setup};
+use solana_sdk::{
+    program_option::COption,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account_interface::address::get_associated_token_address_with_program_id;
+use spl_token_2022_interface::state::AccountState;
+use spl_token_tools::{
+    Funded, FundingOptions, SendOptions, TokenProgram, airdrop, create_ata, create_mint,
+    create_token_account, ensure_funded, get_mint, get_token_account, mint_to, transfer_checked,
+    transfer_checked_to_owner,
+};
+
+#[tokio::test]
+async fn create_mint_initializes_authorities_and_decimals() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+
+        let mint = create_mint(&client, &payer, program, 6, &SendOptions::default())
+            .await
+            .unwrap_sent();
+
+        assert!(mint.state.is_initialized);
+        assert_eq!(mint.state.decimals, 6);
+        assert_eq!(mint.state.supply, 0);
+        assert_eq!(mint.state.mint_authority, COption::Some(payer.pubkey()));
+        assert_eq!(mint.state.freeze_authority, COption::Some(payer.pubkey()));
+        assert_eq!(get_mint(&client, &mint.address).await.unwrap(), mint.state);
+        assert_eq!(
+            TokenProgram::of_mint(&client, &mint.address).await.unwrap(),
+            program
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_token_account_for_another_owner() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let owner = Keypair::new();
+        let mint = create_mint(&client, &payer, program, 9, &SendOptions::default())
+            .await
+            .unwrap_sent();
+
+        let account = create_token_account(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(account.state.mint, mint.address);
+        assert_eq!(account.state.owner, owner.pubkey());
+        assert_eq!(account.state.amount, 0);
+        assert_eq!(account.state.state, AccountState::Initialized);
+    }
+}
+
+#[tokio::test]
+async fn create_ata_at_the_derived_address() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let owner = Keypair::new();
+        let mint = create_mint(&client, &payer, program, 9, &SendOptions::default())
+            .await
+            .unwrap_sent();
+
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &owner.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(
+            ata.address,
+            get_associated_token_address_with_program_id(
+                &owner.pubkey(),
+                &mint.address,
+                &program.id()
+            )
+        );
+        assert_eq!(ata.state.owner, owner.pubkey());
+        assert_eq!(ata.state.mint, mint.address);
+    }
+}
+
+#[tokio::test]
+async fn mint_to_increases_supply_and_balance() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 9, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let minted = mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            1_000_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(minted.mint.supply, 1_000_000_000);
+        assert_eq!(minted.account.amount, 1_000_000_000);
+
+        // Same instruction again must land as a new transaction
+        let minted = mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &ata.address,
+            1_000_000_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        assert_eq!(minted.mint.supply, 2_000_000_000);
+    }
+}
+
+#[tokio::test]
+async fn mint_to_without_authority_fails() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let impostor = funded_keypair(&svm, 1);
+        let mint = create_mint(&client, &payer, program, 9, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let ata = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert!(
+            mint_to(
+                &client,
+                &impostor,
+                program,
+                &mint.address,
+                &ata.address,
+                1,
+                &SendOptions::default()
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(get_mint(&client, &mint.address).await.unwrap().supply, 0);
+    }
+}
+
+#[tokio::test]
+async fn transfer_checked_moves_tokens_between_atas() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let recipient = Keypair::new();
+        let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let destination = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &recipient.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            10_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let transfer = transfer_checked(
+            &client,
+            &payer,
+            program,
+            &source.address,
+            &mint.address,
+            &destination.address,
+            1_000,
+            2,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        assert_eq!(transfer.source.amount, 9_000);
+        assert_eq!(transfer.destination.amount, 1_000);
+        assert_eq!(
+            get_token_account(&client, &destination.address)
+                .await
+                .unwrap()
+                .owner,
+            recipient.pubkey()
+        );
+    }
+}
+
+#[tokio::test]
+async fn transfer_checked_to_owner_creates_a_missing_ata() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let recipient = Keypair::new().pubkey();
+        let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            10_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let (authority, signers) = (payer.pubkey(), [&payer as &dyn Signer]);
+        let options = SendOptions::default();
+        let transfer = || {
+            transfer_checked_to_owner(
+                &client,
+                &payer,
+                program,
+                &source.address,
+                &mint.address,
+                &recipient,
+                &authority,
+                &signers,
+                1_000,
+                2,
+                &options,
+            )
+        };
+
+        let first = transfer().await.unwrap_sent();
+        //The ATA exists by now and is left as is
+        let second = transfer().await.unwrap_sent();
+
+        assert_eq!(first.destination.owner, recipient);
+        assert_eq!(second.destination.amount, 2_000);
+        assert_eq!(second.source.amount, 8_000);
+        let ata = program.ata_address(&recipient, &mint.address);
+        assert_eq!(
+            get_token_account(&client, &ata).await.unwrap().amount,
+            2_000
+        );
+    }
+}
+
+#[tokio::test]
+async fn transfer_checked_rejects_wrong_decimals() {
+    for program in PROGRAMS {
+        let (client, svm) = setup();
+        let payer = funded_keypair(&svm, 10);
+        let mint = create_mint(&client, &payer, program, 2, &SendOptions::default())
+            .await
+            .unwrap_sent();
+        let source = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &payer.pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        let destination = create_ata(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &Keypair::new().pubkey(),
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+        mint_to(
+            &client,
+            &payer,
+            program,
+            &mint.address,
+            &source.address,
+            10_000,
+            &SendOptions::default(),
+        )
+        .await
+        .unwrap_sent();
+
+        let result = transfer_checked(
+            &client,
+            &payer,
+            program,
+            &source.address,
+            &mint.address,
+            &destination.address,
+            1_000,
+            9,
+            &SendOptions::default(),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            get_token_account(&client, &source.address)
+                .await
+                .unwrap()
+                .amount,
+            10_000
+        );
+    }
+}
+
+#[tokio::test]
+async fn airdrop_and_ensure_funded() {
+    let (client, _svm) = setup();
+    let wallet = Keypair::new();
+
+    airdrop(&client, &wallet.pubkey(), 500_000_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get_balance(&wallet.pubkey()).await.unwrap(),
+        500_000_000
+    );
+
+    let options = FundingOptions::default();
+    let funded = ensure_funded(&client, &wallet.pubkey(), 100_000_000, &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        funded,
+        Funded::AlreadyFunded {
+            balance: 500_000_000
+        }
+    );
+
+    let funded = ensure_funded(&client, &wallet.pubkey(), 2_000_000_000, &options)
+        .await
+        .unwrap();
+    assert!(matches!(
+        funded,
+        Funded::Airdropped {
+            lamports: 1_500_000_000,
+            ..
+        }
+    ));
+    assert_eq!(funded.balance(), 2_000_000_000);
+}
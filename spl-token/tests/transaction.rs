@@ -0,0 +1,136 @@
+mod common;
+
+use common::{MAX_PROCESSING_AGE, Sent, funded_keypair, setup, setup_with_controls};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_system_interface::instruction::transfer;
+use spl_token_tools::{
+    Recipient, SendOptions, TokenProgram, create_ata, create_mint, distribute, get_token_account,
+    mint_to,
+    transaction::{MAX_SEND_ATTEMPTS, RecentBlockhash, send_instructions},
+};
+use std::sync::atomic::Ordering;
+
+#[tokio::test]
+async fn recent_blockhash_expires_after_its_last_valid_block_height() {
+    let (client, svm) = setup();
+    let blockhash = RecentBlockhash::fetch(&client).await.unwrap();
+    assert!(!blockhash.is_expired(&client).await.unwrap());
+
+    let height = client.get_block_height().await.unwrap();
+    svm.lock()
+        .unwrap()
+        .warp_to_slot(height + MAX_PROCESSING_AGE + 1);
+
+    assert!(blockhash.is_expired(&client).await.unwrap());
+}
+
+#[tokio::test]
+async fn expired_transactions_are_signed_again_and_resent() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 1);
+    let recipient = Keypair::new().pubkey();
+    controls.drops.store(2, Ordering::SeqCst);
+
+    let signature = send_instructions(
+        &client,
+        &[transfer(&payer.pubkey(), &recipient, 1_000_000)],
+        &payer.pubkey(),
+        &[&payer],
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(controls.drops.load(Ordering::SeqCst), 0);
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 1_000_000);
+    assert!(
+        client
+            .get_signature_status(&signature)
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn sending_gives_up_after_repeated_expiry() {
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 1);
+    let recipient = Keypair::new().pubkey();
+    controls.drops.store(MAX_SEND_ATTEMPTS, Ordering::SeqCst);
+
+    let result = send_instructions(
+        &client,
+        &[transfer(&payer.pubkey(), &recipient, 1_000_000)],
+        &payer.pubkey(),
+        &[&payer],
+        &SendOptions::default(),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(client.get_balance(&recipient).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn distribute_resends_an_expired_batch_once() {
+    let program = TokenProgram::Token;
+    let (client, svm, controls) = setup_with_controls();
+    let payer = funded_keypair(&svm, 10);
+    let mint = create_mint(&client, &payer, program, 0, &SendOptions::default())
+        .await
+        .unwrap_sent();
+    let source = create_ata(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &payer.pubkey(),
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    mint_to(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &source.address,
+        100,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+    let recipient = Recipient {
+        owner: Keypair::new().pubkey(),
+        amount: 40,
+    };
+    let state = std::env::temp_dir().join(format!("resend-{}.json", recipient.owner));
+    controls.drops.store(1, Ordering::SeqCst);
+
+    let distribution = distribute(
+        &client,
+        &payer,
+        program,
+        &mint.address,
+        &[recipient],
+        &state,
+        false,
+        &SendOptions::default(),
+    )
+    .await
+    .unwrap_sent();
+
+    assert_eq!(distribution.sent, 1);
+    assert_eq!(distribution.signatures.len(), 1);
+    let ata = program.ata_address(&recipient.owner, &mint.address);
+    assert_eq!(get_token_account(&client, &ata).await.unwrap().amount, 40);
+    assert_eq!(
+        get_token_account(&client, &source.address)
+            .await
+            .unwrap()
+            .amount,
+        60
+    );
+    std::fs::remove_file(state).unwrap();
+}